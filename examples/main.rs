@@ -25,7 +25,7 @@ fn bit_set() {
     builder.push_bool(true);
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
     println!("{:?}", buffer);
 
     let beads = TypedBeads::new(
@@ -39,7 +39,7 @@ fn bit_set() {
     }
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode_with_types(&mut buffer);
+    builder.encode_with_types(&mut buffer).unwrap();
     println!("{:?}", buffer);
 
     let beads = TypedBeads::new_types_included(
@@ -62,7 +62,7 @@ fn run_numbers() {
     }
 
     let mut out: Vec<u8>  = vec![];
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     println!("Data as beads: {:?}, len: {}", out, out.len());
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
@@ -96,7 +96,7 @@ fn run_strings() {
     }
 
     let mut out: Vec<u8>  = vec![];
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     println!("Data as beads: {:?}, len: {}", out, out.len());
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
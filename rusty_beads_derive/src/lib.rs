@@ -0,0 +1,95 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, format_ident};
+use syn::{parse_macro_input, DeriveInput, Data, Fields, Ident, Token};
+use syn::punctuated::Punctuated;
+
+/// Implements `rusty_beads::columns::BeadsColumns` for a struct with named fields.
+///
+/// Every field is stored in its own Beads sequence. The types of a column can be picked with
+/// `#[beads(U8, U16, None)]`, otherwise `ColumnValue::default_types` of the field type is used.
+#[proc_macro_derive(BeadsColumns, attributes(beads))]
+pub fn derive_beads_columns(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into()
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) if !fields.named.is_empty() => &fields.named,
+            _ => return Err(syn::Error::new_spanned(input, "BeadsColumns needs a struct with at least one named field"))
+        },
+        _ => return Err(syn::Error::new_spanned(input, "BeadsColumns can only be derived for structs"))
+    };
+
+    let mut type_sets = vec![];
+    for field in fields {
+        let mut types = None;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("beads")) {
+            let idents: Vec<Ident> = attr.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?.into_iter().collect();
+            types = Some(quote! { &[#( ::rusty_beads::bead_type::BeadType::#idents ),*] });
+        }
+        let ty = &field.ty;
+        type_sets.push(types.unwrap_or_else(|| quote! {
+            &<#ty as ::rusty_beads::columns::ColumnValue>::default_types()
+        }));
+    }
+
+    let idents: Vec<&Ident> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let names: Vec<String> = idents.iter().map(|i| i.to_string()).collect();
+    let builders: Vec<Ident> = (0..idents.len()).map(|i| format_ident!("builder_{}", i)).collect();
+    let columns: Vec<Ident> = (0..idents.len()).map(|i| format_ident!("column_{}", i)).collect();
+    let iters: Vec<Ident> = (0..idents.len()).map(|i| format_ident!("iter_{}", i)).collect();
+    let indices: Vec<usize> = (0..idents.len()).collect();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::rusty_beads::columns::BeadsColumns for #name #ty_generics #where_clause {
+            fn encode_columns<W>(rows: &[Self], writer: &mut W) -> ::std::result::Result<usize, ::rusty_beads::error::BeadsError> where W: ::std::io::Write {
+                #(
+                    let mut #builders = ::rusty_beads::builder::TypedBeadsBuilder::new(
+                        &::rusty_beads::bead_type::BeadTypeSet::new(#type_sets)
+                    )?;
+                )*
+                for (row, value) in rows.iter().enumerate() {
+                    #(
+                        ::rusty_beads::columns::push_value(&value.#idents, &mut #builders, #names, row)?;
+                    )*
+                }
+                let builders: ::std::vec::Vec<::std::boxed::Box<dyn ::rusty_beads::builder::BeadsBuilder>> = vec![
+                    #( ::std::boxed::Box::new(#builders) ),*
+                ];
+                Ok(::rusty_beads::builder::IndexedBeadsBuilder::encode_from_beads_builders(writer, builders)?)
+            }
+
+            fn decode_columns(buffer: &[u8]) -> ::std::result::Result<::std::vec::Vec<Self>, ::rusty_beads::error::BeadsError> {
+                let indexed = ::rusty_beads::sequence::IndexedBeads::new(buffer)?;
+                #(
+                    let #columns = indexed.get_typed(
+                        #indices,
+                        &::rusty_beads::bead_type::BeadTypeSet::new(#type_sets)
+                    )?;
+                )*
+                let len = column_0.len();
+                #(
+                    ::rusty_beads::columns::check_column_len(#indices, #columns.len(), len)?;
+                )*
+                #( let mut #iters = #columns.iter(); )*
+                let mut rows = ::std::vec::Vec::with_capacity(len);
+                for row in 0..len {
+                    rows.push(#name {
+                        #( #idents: ::rusty_beads::columns::next_value(&mut #iters, row, len)?, )*
+                    });
+                }
+                Ok(rows)
+            }
+        }
+    })
+}
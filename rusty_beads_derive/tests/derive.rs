@@ -0,0 +1,86 @@
+use rusty_beads::columns::BeadsColumns;
+use rusty_beads::error::BeadsError;
+use rusty_beads::builder::{TypedBeadsBuilder, IndexedBeadsBuilder, BeadsBuilder};
+use rusty_beads::bead_type::{BeadTypeSet, BeadType};
+use rusty_beads::sequence::IndexedBeads;
+use rusty_beads_derive::BeadsColumns;
+
+#[derive(BeadsColumns, PartialEq, Debug)]
+struct Person {
+    name: String,
+    #[beads(U8)]
+    age: u32,
+    height: Option<f32>,
+    #[beads(TrueFlag, FalseFlag)]
+    active: bool,
+    #[beads(Vlq, None)]
+    score: Option<u64>,
+}
+
+fn people() -> Vec<Person> {
+    vec![
+        Person { name: "Maxim".to_string(), age: 40, height: Some(1.5), active: true, score: Some(300_000) },
+        Person { name: "Alex".to_string(), age: 7, height: None, active: false, score: None },
+        Person { name: "🤪".to_string(), age: 255, height: Some(0.25), active: true, score: Some(0) },
+    ]
+}
+
+#[test]
+fn roundtrip() {
+    let mut buffer = vec![];
+    let written = Person::encode_columns(&people(), &mut buffer).unwrap();
+    assert_eq!(written, buffer.len());
+    assert_eq!(Person::decode_columns(&buffer).unwrap(), people());
+}
+
+#[test]
+fn columns_are_plain_indexed_beads() {
+    let mut buffer = vec![];
+    Person::encode_columns(&people(), &mut buffer).unwrap();
+
+    let indexed = IndexedBeads::new(&buffer).unwrap();
+    assert_eq!(indexed.len(), 5);
+    let ages = indexed.get_typed(1, &BeadTypeSet::new(&[BeadType::U8])).unwrap();
+    let ages: Vec<u128> = ages.iter().map(|b| b.to_uint()).collect();
+    assert_eq!(ages, vec![40, 7, 255]);
+}
+
+#[test]
+fn empty_rows() {
+    let mut buffer = vec![];
+    Person::encode_columns(&[], &mut buffer).unwrap();
+    assert_eq!(Person::decode_columns(&buffer).unwrap(), vec![]);
+}
+
+#[test]
+fn value_outside_of_column_types() {
+    let mut rows = people();
+    rows[2].age = 256;
+    let mut buffer = vec![];
+    let error = Person::encode_columns(&rows, &mut buffer).err().unwrap();
+    assert_eq!(error.to_string(), "Field age in row 2 is invalid: Could not push value 256");
+}
+
+#[test]
+fn columns_with_different_lengths() {
+    #[derive(BeadsColumns, PartialEq, Debug)]
+    struct Pair {
+        #[beads(U8)]
+        a: u8,
+        #[beads(U8)]
+        b: u8,
+    }
+
+    let types = BeadTypeSet::new(&[BeadType::U8]);
+    let mut a = TypedBeadsBuilder::new(&types).unwrap();
+    a.push_uint(1);
+    a.push_uint(2);
+    let mut b = TypedBeadsBuilder::new(&types).unwrap();
+    b.push_uint(1);
+    let builders: Vec<Box<dyn BeadsBuilder>> = vec![Box::new(a), Box::new(b)];
+    let mut buffer = vec![];
+    IndexedBeadsBuilder::encode_from_beads_builders(&mut buffer, builders).unwrap();
+
+    let error = Pair::decode_columns(&buffer).err().unwrap();
+    assert!(matches!(error, BeadsError::ColumnLengthMismatch { column: 1, len: 1, rows: 2 }));
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::cmp::max;
+use crate::bead_type::{BeadType, BeadTypeSet};
+use crate::vlq::VlqByteSize;
+use crate::error::BeadsError;
+use crate::decimal::Decimal;
+
+/// A type set together with the size of the sequence encoded with it.
+#[derive(Debug)]
+pub struct TypeSetCandidate {
+    pub types: BeadTypeSet,
+    /// Number of bytes `TypedBeadsBuilder::encode` would write for the analyzed values.
+    pub size: usize,
+}
+
+/// Collects values the same way a `TypedBeadsBuilder` does and finds the type set
+/// which encodes them with the least number of bytes.
+pub struct TypeSetAnalyzer {
+    // number of values per list of (type, payload size) the value can be stored as,
+    // in the order the builder tries the types
+    profiles: HashMap<Vec<(BeadType, usize)>, usize>,
+    // types every set has to contain, like `Utf8` for strings
+    required: u32,
+    // payload of values which can be stored only in one way
+    fixed_payload: usize,
+    count: usize,
+}
+
+impl Default for TypeSetAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypeSetAnalyzer {
+    pub fn new() -> TypeSetAnalyzer {
+        TypeSetAnalyzer {
+            profiles: HashMap::new(),
+            required: 0,
+            fixed_payload: 0,
+            count: 0
+        }
+    }
+
+    pub fn push_uint(&mut self, value: u128) -> bool {
+        self.add_profile(BeadType::cases_for_uint(), |t, buffer| t.push_uint(value, buffer))
+    }
+
+    pub fn push_int(&mut self, value: i128) -> bool {
+        self.add_profile(BeadType::cases_for_int(), |t, buffer| t.push_int(value, buffer))
+    }
+
+    pub fn push_double(&mut self, value: f64) -> bool {
+        self.push_double_with_accuracy(value, 0.0)
+    }
+
+    pub fn push_double_with_accuracy(&mut self, value: f64, accuracy: f64) -> bool {
+        self.add_profile(BeadType::cases_for_double(), |t, buffer| t.push_double(value, accuracy, buffer))
+    }
+
+    /// Profiles `value` like `TypedBeadsBuilder::push_decimal` does for a sequence of the given scale.
+    pub fn push_decimal(&mut self, value: Decimal, scale: u8) -> bool {
+        match value.rescale(scale) {
+            Some(value) => self.push_int(value.mantissa()),
+            None => false
+        }
+    }
+
+    pub fn push_string(&mut self, value: &str) -> bool {
+        self.push_bytes_as(BeadType::Utf8, value.len())
+    }
+
+    pub fn push_bytes(&mut self, value: &[u8]) -> bool {
+        self.push_bytes_as(BeadType::Bytes, value.len())
+    }
+
+    pub fn push_bool(&mut self, value: bool) -> bool {
+        self.required |= if value { BeadType::TrueFlag as u32 } else { BeadType::FalseFlag as u32 };
+        self.count += 1;
+        true
+    }
+
+    pub fn push_none(&mut self) -> bool {
+        self.required |= BeadType::None as u32;
+        self.count += 1;
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Type sets which can store all pushed values, smallest encoded size first, at most `alternatives` of them.
+    /// Sets with a type no value would be stored as are left out, as they can only be larger.
+    /// Fails with `TypeSetSize(0)` if no values were pushed.
+    pub fn analyze(&self, alternatives: usize) -> Result<Vec<TypeSetCandidate>, BeadsError> {
+        if self.count == 0 {
+            return Err(BeadsError::TypeSetSize(0));
+        }
+        let mut types: Vec<BeadType> = vec![];
+        for profile in self.profiles.keys() {
+            for (t, _) in profile {
+                if !types.contains(t) {
+                    types.push(*t);
+                }
+            }
+        }
+        // profiles with the types replaced by their position in `types`, so a subset is a bit mask
+        let profiles: Vec<(Vec<(usize, usize)>, usize)> = self.profiles.iter().map(|(profile, count)| {
+            (profile.iter().map(|(t, size)| (types.iter().position(|c| c == t).unwrap(), *size)).collect(), *count)
+        }).collect();
+        let required_types = self.required.count_ones() as usize;
+
+        let mut candidates = vec![];
+        let first_mask = if profiles.is_empty() { 0 } else { 1 };
+        for mask in first_mask..(1u32 << types.len()) {
+            let type_count = mask.count_ones() as usize + required_types;
+            if type_count > 16 {
+                continue;
+            }
+            if let Some(payload) = Self::payload(&profiles, mask) {
+                let mut value = self.required;
+                for (i, t) in types.iter().enumerate() {
+                    if mask & (1 << i) != 0 {
+                        value |= *t as u32;
+                    }
+                }
+                candidates.push(TypeSetCandidate {
+                    types: BeadTypeSet::from(value),
+                    size: self.encoded_size(type_count, self.fixed_payload + payload)
+                });
+            }
+        }
+        candidates.sort_by_key(|c| (c.size, c.types.size()));
+        candidates.truncate(max(alternatives, 1));
+        Ok(candidates)
+    }
+
+    /// Payload of all profiles with the types in `mask`, `None` if a value can't be stored
+    /// or one of the types would not be used.
+    fn payload(profiles: &[(Vec<(usize, usize)>, usize)], mask: u32) -> Option<usize> {
+        let mut payload = 0;
+        let mut used = 0u32;
+        for (profile, count) in profiles {
+            let (index, size) = profile.iter().find(|(index, _)| mask & (1 << *index) != 0)?;
+            used |= 1 << *index;
+            payload += size * count;
+        }
+        if used != mask {
+            return None;
+        }
+        Some(payload)
+    }
+
+    fn encoded_size(&self, type_count: usize, payload: usize) -> usize {
+        let data = if type_count == 1 {
+            max(payload, 1)
+        } else {
+            let tags_per_byte = match type_count {
+                1..=2 => 8,
+                3..=4 => 4,
+                _ => 2
+            };
+            self.count.div_ceil(tags_per_byte) + payload
+        };
+        self.count.vlq_byte_size() + data
+    }
+
+    fn add_profile<F>(&mut self, cases: Vec<BeadType>, push: F) -> bool where F: Fn(BeadType, &mut [u8]) -> (bool, usize) {
+        let mut buffer = [0u8; 20];
+        let mut profile = vec![];
+        for t in cases {
+            let (added, size) = push(t, &mut buffer);
+            if added {
+                profile.push((t, size));
+            }
+        }
+        if profile.is_empty() {
+            return false;
+        }
+        *self.profiles.entry(profile).or_insert(0) += 1;
+        self.count += 1;
+        true
+    }
+
+    fn push_bytes_as(&mut self, bead_type: BeadType, len: usize) -> bool {
+        self.required |= bead_type as u32;
+        self.fixed_payload += len.vlq_byte_size() + len;
+        self.count += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyzer::TypeSetAnalyzer;
+    use crate::bead_type::{BeadType, BeadTypeSet};
+    use crate::builder::TypedBeadsBuilder;
+    use crate::error::BeadsError;
+
+    enum Value {
+        Uint(u128),
+        Int(i128),
+        Double(f64, f64),
+        Str(&'static str),
+        None,
+    }
+
+    fn analyze(values: &[Value], alternatives: usize) -> Vec<(BeadTypeSet, usize)> {
+        let mut analyzer = TypeSetAnalyzer::new();
+        for v in values {
+            let pushed = match v {
+                Value::Uint(v) => analyzer.push_uint(*v),
+                Value::Int(v) => analyzer.push_int(*v),
+                Value::Double(v, accuracy) => analyzer.push_double_with_accuracy(*v, *accuracy),
+                Value::Str(v) => analyzer.push_string(v),
+                Value::None => analyzer.push_none(),
+            };
+            assert!(pushed);
+        }
+        let candidates = analyzer.analyze(alternatives).ok().unwrap();
+        // the projected size is the size of the encoded builder
+        for candidate in candidates.iter() {
+            let mut builder = TypedBeadsBuilder::new(&candidate.types).ok().unwrap();
+            for v in values {
+                let pushed = match v {
+                    Value::Uint(v) => builder.push_uint(*v),
+                    Value::Int(v) => builder.push_int(*v),
+                    Value::Double(v, accuracy) => builder.push_double_with_accuracy(*v, *accuracy),
+                    Value::Str(v) => builder.push_string(v),
+                    Value::None => builder.push_none(),
+                };
+                assert!(pushed);
+            }
+            let mut buffer = vec![];
+            assert_eq!(builder.encode(&mut buffer).unwrap(), candidate.size);
+        }
+        candidates.into_iter().map(|c| (c.types, c.size)).collect()
+    }
+
+    #[test]
+    fn picks_smallest_type_set() {
+        let small: Vec<Value> = (0..100).map(|i| Value::Uint(i % 50)).collect();
+        assert_eq!(analyze(&small, 1), vec![(BeadTypeSet::new(&[BeadType::U8]), 101)]);
+
+        let mut mixed: Vec<Value> = (0..100).map(|i| Value::Int(i * 3 - 20)).collect();
+        mixed.push(Value::Int(70_000));
+        mixed.push(Value::None);
+        let candidates = analyze(&mixed, 4);
+        assert_eq!(candidates.len(), 4);
+        assert!(candidates.windows(2).all(|w| w[0].1 <= w[1].1));
+        // values in 128..256 are stored in one byte as U8 instead of two as VlqZ
+        assert_eq!(candidates[0].0, BeadTypeSet::new(&[BeadType::U8, BeadType::VlqZ, BeadType::None]));
+        // U64 would never be picked, as I32 holds all the values
+        assert!(analyze(&mixed, 1000).iter().all(|c| c.0 != BeadTypeSet::new(&[BeadType::I8, BeadType::I32, BeadType::None, BeadType::U64])));
+
+        let strings = vec![Value::Str("Hello"), Value::None, Value::Str(""), Value::Double(0.5, 0.0), Value::Double(2.0001, 0.001)];
+        let candidates = analyze(&strings, 3);
+        assert_eq!(candidates[0].0, BeadTypeSet::new(&[BeadType::Utf8, BeadType::None, BeadType::U8, BeadType::F16]));
+        for (types, _) in candidates {
+            assert!(types.contains(&BeadType::Utf8) && types.contains(&BeadType::None));
+        }
+    }
+
+    #[test]
+    fn only_required_types() {
+        assert_eq!(analyze(&[Value::None, Value::None], 5), vec![(BeadTypeSet::new(&[BeadType::None]), 2)]);
+        let mut analyzer = TypeSetAnalyzer::new();
+        assert!(matches!(analyzer.analyze(3).err().unwrap(), BeadsError::TypeSetSize(0)));
+        analyzer.push_bool(true);
+        analyzer.push_bool(false);
+        analyzer.push_bytes(&[1, 2, 3]);
+        let candidates = analyzer.analyze(3).ok().unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].types, BeadTypeSet::new(&[BeadType::TrueFlag, BeadType::FalseFlag, BeadType::Bytes]));
+        assert_eq!(candidates[0].size, 1 + 1 + 4);
+    }
+}
@@ -1,7 +1,11 @@
 use crate::vlq::{add_as_vlq, add_as_vlqz};
 use half::f16;
+use crate::error::BeadsError;
+use crate::decimal::MAX_SCALE;
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-#[derive(PartialEq, Hash, Clone, Copy)]
+#[derive(PartialEq, Hash, Clone, Copy, Debug)]
 pub enum BeadType {
     None = 1,
     TrueFlag = 1 << 1,
@@ -111,14 +115,11 @@ impl BeadType {
     }
 
     pub(crate) fn has_no_data(&self) -> bool {
-        return match self {
-            BeadType::None | BeadType::TrueFlag | BeadType::FalseFlag => true,
-            _ => false
-        }
+        matches!(self, BeadType::None | BeadType::TrueFlag | BeadType::FalseFlag)
     }
 
     pub(crate) fn data_size(&self) -> u8 {
-        return match self {
+        match self {
             BeadType::None | BeadType::TrueFlag | BeadType::FalseFlag => 0,
             BeadType::U8 | BeadType::I8 => 1,
             BeadType::U16 | BeadType::I16 | BeadType::F16 => 2,
@@ -130,7 +131,7 @@ impl BeadType {
     }
 
     pub(crate) fn push_uint(&self, value: u128, buffer: &mut [u8]) -> (bool, usize) {
-        return match self {
+        match self {
             BeadType::U8 => {
                 if value >> 8 == 0 {
                     buffer[0] = value as u8;
@@ -260,7 +261,7 @@ impl BeadType {
     }
 
     pub(crate) fn push_int(&self, value: i128, buffer: &mut [u8]) -> (bool, usize) {
-        return match self {
+        match self {
             BeadType::I8 => {
                 let v = value as i8;
                 if value == v as i128 {
@@ -354,7 +355,7 @@ impl BeadType {
             }
             BeadType::VlqZ => {
                 let length = add_as_vlqz(buffer, value);
-                return (true, length)
+                (true, length)
             }
             BeadType::F32 => {
                 let f = value as f32;
@@ -391,7 +392,7 @@ impl BeadType {
     }
 
     pub(crate) fn push_double(&self, value: f64, accuracy: f64, buffer: &mut [u8]) -> (bool, usize) {
-        return match self {
+        match self {
             BeadType::I8 => {
                 let v = value as i8;
                 if (value - v as f64).abs() <= accuracy {
@@ -474,7 +475,7 @@ impl BeadType {
             BeadType::F64 => {
                 let b = value.to_le_bytes();
                 buffer[..b.len()].copy_from_slice(&b);
-                return (true, b.len())
+                (true, b.len())
             }
             BeadType::F16 => {
                 let v = f16::from_f32(value as f32);
@@ -492,6 +493,93 @@ impl BeadType {
 }
 
 
+/// Unit of timestamps and durations, which are stored as integer counts of the unit.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum TimeUnit {
+    Seconds = 1,
+    Millis = 2,
+    Micros = 3,
+    Nanos = 4,
+}
+
+impl TimeUnit {
+    pub fn nanos_per_unit(&self) -> u128 {
+        match self {
+            TimeUnit::Seconds => 1_000_000_000,
+            TimeUnit::Millis => 1_000_000,
+            TimeUnit::Micros => 1_000,
+            TimeUnit::Nanos => 1
+        }
+    }
+
+    /// Number of whole units in `duration`.
+    pub fn count(&self, duration: Duration) -> u128 {
+        duration.as_nanos() / self.nanos_per_unit()
+    }
+
+    /// Number of units since the Unix epoch, rounded down for times before the epoch.
+    pub fn count_since_epoch(&self, time: SystemTime) -> i128 {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => self.count(duration) as i128,
+            Err(e) => -(e.duration().as_nanos().div_ceil(self.nanos_per_unit()) as i128)
+        }
+    }
+
+    /// The duration of `count` units, fails if it does not fit into a `Duration`.
+    pub fn duration(&self, count: u128) -> Result<Duration, BeadsError> {
+        let out_of_range = || BeadsError::OutOfRange { value: count.to_string(), target: "Duration" };
+        let nanos = count.checked_mul(self.nanos_per_unit()).ok_or_else(out_of_range)?;
+        let seconds = u64::try_from(nanos / 1_000_000_000).map_err(|_| out_of_range())?;
+        Ok(Duration::new(seconds, (nanos % 1_000_000_000) as u32))
+    }
+
+    /// The time `count` units after (or before, if negative) the Unix epoch.
+    pub fn timestamp(&self, count: i128) -> Result<SystemTime, BeadsError> {
+        let out_of_range = || BeadsError::OutOfRange { value: count.to_string(), target: "SystemTime" };
+        let duration = self.duration(count.unsigned_abs()).map_err(|_| out_of_range())?;
+        let time = if count >= 0 { UNIX_EPOCH.checked_add(duration) } else { UNIX_EPOCH.checked_sub(duration) };
+        time.ok_or_else(out_of_range)
+    }
+
+    fn from_bits(bits: u32) -> Option<TimeUnit> {
+        match bits {
+            1 => Some(TimeUnit::Seconds),
+            2 => Some(TimeUnit::Millis),
+            3 => Some(TimeUnit::Micros),
+            4 => Some(TimeUnit::Nanos),
+            _ => None
+        }
+    }
+}
+
+/// Declares that the integer values of a sequence are timestamps or durations.
+/// It is kept in the type set and written by `encode_with_types`, so readers know the unit.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum TimeKind {
+    /// Units since the Unix epoch.
+    Timestamp(TimeUnit),
+    Duration(TimeUnit),
+}
+
+impl TimeKind {
+    pub fn unit(&self) -> TimeUnit {
+        match self {
+            TimeKind::Timestamp(unit) | TimeKind::Duration(unit) => *unit
+        }
+    }
+}
+
+// the types take the lower 20 bits of a type set, the decimal scale and the time kind are stored above them:
+// 4 bits for the scale, 3 bits for the time unit, one bit which marks durations and one bit which marks decimals
+const TYPE_BITS: u32 = (1 << 20) - 1;
+const SCALE_SHIFT: u32 = 20;
+const SCALE_BITS: u32 = 0b1111 << SCALE_SHIFT;
+const TIME_UNIT_SHIFT: u32 = 24;
+const TIME_UNIT_BITS: u32 = 0b111 << TIME_UNIT_SHIFT;
+const DURATION_BIT: u32 = 1 << 27;
+const DECIMAL_BIT: u32 = 1 << 28;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct BeadTypeSet {
     value: u32
 }
@@ -513,12 +601,67 @@ impl BeadTypeSet {
     }
 
     pub fn size(&self) -> usize {
-        self.value.count_ones() as usize
+        (self.value & TYPE_BITS).count_ones() as usize
+    }
+
+    /// The same types, marked as timestamps or durations of the given unit.
+    pub fn with_time(&self, kind: TimeKind) -> BeadTypeSet {
+        let (unit, duration) = match kind {
+            TimeKind::Timestamp(unit) => (unit, 0),
+            TimeKind::Duration(unit) => (unit, DURATION_BIT)
+        };
+        BeadTypeSet {
+            value: (self.value & !(TIME_UNIT_BITS | DURATION_BIT)) | ((unit as u32) << TIME_UNIT_SHIFT) | duration
+        }
+    }
+
+    /// The time kind set with `with_time`, `None` if there is none or the bits are not a known unit.
+    pub fn time(&self) -> Option<TimeKind> {
+        let unit = TimeUnit::from_bits((self.value & TIME_UNIT_BITS) >> TIME_UNIT_SHIFT)?;
+        if self.value & DURATION_BIT != 0 {
+            Some(TimeKind::Duration(unit))
+        } else {
+            Some(TimeKind::Timestamp(unit))
+        }
+    }
+
+    /// The same types, marked as decimals with `scale` fraction digits, see `TypedBeadsBuilder::push_decimal`.
+    /// Fails if `scale` is larger than `MAX_SCALE`.
+    pub fn with_scale(&self, scale: u8) -> Result<BeadTypeSet, BeadsError> {
+        if scale > MAX_SCALE {
+            return Err(BeadsError::OutOfRange { value: scale.to_string(), target: "decimal scale" });
+        }
+        Ok(BeadTypeSet {
+            value: (self.value & !SCALE_BITS) | ((scale as u32) << SCALE_SHIFT) | DECIMAL_BIT
+        })
+    }
+
+    /// The decimal scale set with `with_scale`, `None` if the values are not decimals.
+    pub fn scale(&self) -> Option<u8> {
+        if self.value & DECIMAL_BIT == 0 {
+            return None;
+        }
+        Some(((self.value & SCALE_BITS) >> SCALE_SHIFT) as u8)
     }
 
     pub fn bytes(&self) -> [u8;4] {
         self.value.to_le_bytes()
     }
+
+    /// The types of the set in tag order, checked to be a valid Beads type set.
+    pub(crate) fn types_by_priority(&self) -> Result<Vec<BeadType>, BeadsError> {
+        let mut types = vec![];
+        for t in BeadType::cases_by_priority() {
+            if self.contains(&t) {
+                types.push(t)
+            }
+        }
+        // unknown bits in a type set read from a buffer do not count as types
+        if types.is_empty() || types.len() > 16 {
+            return Err(BeadsError::TypeSetSize(types.len()));
+        }
+        Ok(types)
+    }
 }
 
 impl From<u32> for BeadTypeSet {
@@ -1,13 +1,21 @@
 use std::collections::HashMap;
-use crate::bead_type::{BeadType, BeadTypeSet};
+use crate::bead_type::{BeadType, BeadTypeSet, TimeKind};
 use std::cmp::max;
-use crate::vlq::{add_as_vlq, VlqByteSize};
+use crate::vlq::{add_as_vlq, add_as_padded_vlq, VlqByteSize};
 use std::io;
+use std::time::{Duration, SystemTime};
 use std::cell::{RefCell, RefMut};
 use std::borrow::{BorrowMut};
+use crate::error::BeadsError;
+use crate::sequence::TypedBeads;
+use crate::reference::BeadReference;
+use crate::decimal::Decimal;
 
+// `len` is the size of the encoded sequence, which is never empty
+#[allow(clippy::len_without_is_empty)]
 pub trait BeadsBuilder {
-    fn encode<'a>(&self, writer: RefMut<dyn io::Write + 'a>);
+    /// Writes the encoded sequence and returns the number of bytes written.
+    fn encode<'a>(&self, writer: RefMut<dyn io::Write + 'a>) -> io::Result<usize>;
     fn len(&self) -> usize;
 }
 
@@ -18,13 +26,12 @@ pub struct TypedBeadsBuilder {
     flag_pointer: usize,
     data_pointer: usize,
     type_index: HashMap<BeadType, u8>,
+    time: Option<TimeKind>,
+    scale: Option<u8>,
 }
 
 impl TypedBeadsBuilder {
-    pub fn new(types: &BeadTypeSet) -> Result<TypedBeadsBuilder, &'static str> {
-        if types.size() < 1 || types.size() > 16 {
-            return Err("Beads sequence can carry only 1..=16 types");
-        }
+    pub fn new(types: &BeadTypeSet) -> Result<TypedBeadsBuilder, BeadsError> {
         let mut type_index = HashMap::new();
         let mut index = 0u8;
         for t in BeadType::cases_by_priority() {
@@ -33,6 +40,9 @@ impl TypedBeadsBuilder {
                 index += 1;
             }
         }
+        if type_index.is_empty() || type_index.len() > 16 {
+            return Err(BeadsError::TypeSetSize(type_index.len()));
+        }
 
         Ok(TypedBeadsBuilder {
             buffer: vec![0; 1000],
@@ -40,10 +50,46 @@ impl TypedBeadsBuilder {
             flag_count: 0,
             flag_pointer: 0,
             data_pointer: 0,
-            type_index
+            type_index,
+            time: types.time(),
+            scale: types.scale()
         })
     }
 
+    /// Continues an encoded sequence, so new values can be appended without pushing the old ones again.
+    /// The buffer has to hold exactly one sequence, which is validated first.
+    pub fn from_encoded(buffer: &[u8], types: &BeadTypeSet) -> Result<TypedBeadsBuilder, BeadsError> {
+        let mut builder = TypedBeadsBuilder::new(types)?;
+        builder.restore(&TypedBeads::new(buffer, types)?)?;
+        Ok(builder)
+    }
+
+    /// Same as `from_encoded`, for a buffer written by `encode_with_types`.
+    pub fn from_encoded_types_included(buffer: &[u8]) -> Result<TypedBeadsBuilder, BeadsError> {
+        let beads = TypedBeads::new_types_included(buffer)?;
+        let types = BeadTypeSet::from(u32::from_le_bytes(BeadReference::clone_into_array(&buffer[..4])));
+        let mut builder = TypedBeadsBuilder::new(&types)?;
+        builder.restore(&beads)?;
+        Ok(builder)
+    }
+
+    fn restore(&mut self, beads: &TypedBeads) -> Result<(), BeadsError> {
+        let (count, tag_cursor, data_cursor) = beads.validated_end()?;
+        let data = beads.data();
+        // the decoder is positioned on the tag byte of the last element, same as the flag pointer
+        self.count = count;
+        self.flag_count = count;
+        self.flag_pointer = tag_cursor;
+        self.data_pointer = data_cursor;
+        self.buffer = vec![0; max(data.len(), 1000)];
+        self.buffer[..data.len()].copy_from_slice(data);
+        // bits of the current tag byte which are not used yet could be set by another encoder
+        if self.compute_flag_info().0 != 0 {
+            self.reset_flag();
+        }
+        Ok(())
+    }
+
     pub fn push_bool(&mut self, value: bool) -> bool {
         let mut type_index = 255u8;
         if value {
@@ -60,7 +106,7 @@ impl TypedBeadsBuilder {
             self.count += 1;
             return true;
         }
-        return false;
+        false
     }
 
     pub fn push_none(&mut self) -> bool {
@@ -74,7 +120,7 @@ impl TypedBeadsBuilder {
             self.count += 1;
             return true;
         }
-        return false;
+        false
     }
 
     pub fn push_string(&mut self, value: &str) -> bool {
@@ -93,7 +139,7 @@ impl TypedBeadsBuilder {
             self.count += 1;
             return true;
         }
-        return false;
+        false
     }
 
     pub fn push_bytes(&mut self, value: &[u8]) -> bool {
@@ -112,12 +158,27 @@ impl TypedBeadsBuilder {
             self.count += 1;
             return true;
         }
-        return false;
+        false
+    }
+
+    /// Pushes the sequence encoded by `beads` as a `Bytes` element, which can be read back
+    /// with `BeadReference::as_typed_beads` or `as_indexed_beads`.
+    pub fn push_beads(&mut self, beads: &dyn BeadsBuilder) -> bool {
+        if !self.type_index.contains_key(&BeadType::Bytes) {
+            return false;
+        }
+        let mut buffer: Vec<u8> = Vec::with_capacity(beads.len());
+        let writer = RefCell::new(&mut buffer);
+        if beads.encode(writer.borrow_mut()).is_err() {
+            return false;
+        }
+        self.push_bytes(&buffer)
     }
 
     pub fn push_uint(&mut self, value: u128) -> bool {
         let start = max(self.flag_pointer+1, self.data_pointer);
         self.grow_buffer_if_needed(start, 16);
+        let (flag_pointer, flag_count) = (self.flag_pointer, self.flag_count);
         for t in BeadType::cases_for_uint() {
             let mut type_index = 255u8;
             if let Some(_type_index) = self.type_index.get(&t) {
@@ -137,12 +198,16 @@ impl TypedBeadsBuilder {
                 }
             }
         }
+        // nothing fit, so the tag slot must not be kept for the next value
+        self.flag_pointer = flag_pointer;
+        self.flag_count = flag_count;
         false
     }
 
     pub fn push_int(&mut self, value: i128) -> bool {
         let start = max(self.flag_pointer+1, self.data_pointer);
         self.grow_buffer_if_needed(start, 16);
+        let (flag_pointer, flag_count) = (self.flag_pointer, self.flag_count);
         for t in BeadType::cases_for_int() {
             let mut type_index = 255u8;
             if let Some(_type_index) = self.type_index.get(&t) {
@@ -161,6 +226,9 @@ impl TypedBeadsBuilder {
                 }
             }
         }
+        // nothing fit, so the tag slot must not be kept for the next value
+        self.flag_pointer = flag_pointer;
+        self.flag_count = flag_count;
         false
     }
 
@@ -168,9 +236,37 @@ impl TypedBeadsBuilder {
         self.push_double_with_accuracy(value, 0.0)
     }
 
+    /// Pushes the mantissa of `value` as an int, after scaling it to the scale declared with `BeadTypeSet::with_scale`.
+    /// Returns false if the builder was not created for decimals or `value` has more fraction digits than the scale.
+    pub fn push_decimal(&mut self, value: Decimal) -> bool {
+        match self.scale.and_then(|scale| value.rescale(scale)) {
+            Some(value) => self.push_int(value.mantissa()),
+            None => false
+        }
+    }
+
+    /// Pushes `time` as an int of the unit declared with `BeadTypeSet::with_time`.
+    /// Returns false if the builder was not created for timestamps.
+    pub fn push_timestamp(&mut self, time: SystemTime) -> bool {
+        match self.time {
+            Some(TimeKind::Timestamp(unit)) => self.push_int(unit.count_since_epoch(time)),
+            _ => false
+        }
+    }
+
+    /// Pushes `duration` as an uint of the unit declared with `BeadTypeSet::with_time`.
+    /// Returns false if the builder was not created for durations.
+    pub fn push_duration(&mut self, duration: Duration) -> bool {
+        match self.time {
+            Some(TimeKind::Duration(unit)) => self.push_uint(unit.count(duration)),
+            _ => false
+        }
+    }
+
     pub fn push_double_with_accuracy(&mut self, value: f64, accuracy: f64) -> bool {
         let start = max(self.flag_pointer+1, self.data_pointer);
         self.grow_buffer_if_needed(start, 8);
+        let (flag_pointer, flag_count) = (self.flag_pointer, self.flag_count);
         for t in BeadType::cases_for_double() {
             let mut type_index = 255u8;
             if let Some(_type_index) = self.type_index.get(&t) {
@@ -190,19 +286,219 @@ impl TypedBeadsBuilder {
                 }
             }
         }
+        // nothing fit, so the tag slot must not be kept for the next value
+        self.flag_pointer = flag_pointer;
+        self.flag_count = flag_count;
         false
     }
 
-    pub fn encode<W>(&self, writer: &mut W) where W: io::Write {
-        <dyn BeadsBuilder>::encode(self, RefCell::new(writer).borrow_mut());
+    /// Pushes a bead read from another sequence. If the type of the bead is part of the builder,
+    /// the payload is copied as is, otherwise the value is pushed with `push_uint`, `push_int`,
+    /// `push_double` or `push_bool`. Strings can also be pushed as bytes.
+    pub fn push_bead(&mut self, bead: &BeadReference) -> bool {
+        if let Some(type_index) = self.type_index.get(&bead.bead_type).copied() {
+            self.add_flag(type_index);
+            if !bead.bead_type.has_no_data() {
+                let start = self.data_start();
+                self.grow_buffer_if_needed(start, bead.buffer.len() + 10);
+                let mut vlq_len = 0;
+                if bead.is_string() || bead.is_bytes() {
+                    vlq_len = add_as_vlq(self.buffer[start..].as_mut(), bead.buffer.len() as u128);
+                }
+                self.buffer[start+vlq_len..start+vlq_len+bead.buffer.len()].copy_from_slice(bead.buffer);
+                self.data_pointer = start + vlq_len + bead.buffer.len();
+            }
+            self.count += 1;
+            return true;
+        }
+        if bead.is_uint() {
+            return bead.try_to_uint().map(|v| self.push_uint(v)).unwrap_or(false);
+        }
+        if bead.is_int() {
+            return bead.try_to_int().map(|v| self.push_int(v)).unwrap_or(false);
+        }
+        if bead.is_float() {
+            return bead.try_to_float().map(|v| self.push_double(v)).unwrap_or(false);
+        }
+        if bead.is_bool() {
+            return self.push_bool(bead.is_true());
+        }
+        if bead.is_string() {
+            return self.push_bytes(bead.buffer);
+        }
+        false
+    }
+
+    /// Pushes all elements of `beads`. If the sequence has the same types, time kind and scale as the builder
+    /// and the builder is at the start of a tag byte, the encoded elements are copied in one go,
+    /// otherwise every element goes through `push_bead`. Timestamps and durations of another unit
+    /// are converted to the unit of the builder, like with `push_timestamp` and `push_duration`,
+    /// decimals of another scale are rescaled like with `push_decimal`.
+    /// Fails with `KindMismatch` if the sequence holds other kinds of values than the builder,
+    /// for example durations instead of timestamps. On error the builder is left unchanged.
+    pub fn append(&mut self, beads: &TypedBeads) -> Result<(), BeadsError> {
+        let (count, tag_cursor, data_cursor) = beads.validated_end()?;
+        let mismatch = || BeadsError::KindMismatch {
+            found: describe_kind(beads.scale(), beads.time()),
+            expected: describe_kind(self.scale, self.time)
+        };
+        let same_unit = match (self.time, beads.time()) {
+            (None, None) => true,
+            (Some(TimeKind::Timestamp(a)), Some(TimeKind::Timestamp(b))) | (Some(TimeKind::Duration(a)), Some(TimeKind::Duration(b))) => a == b,
+            _ => return Err(mismatch())
+        };
+        let same_scale = match (self.scale, beads.scale()) {
+            (None, None) => true,
+            (Some(a), Some(b)) => a == b,
+            _ => return Err(mismatch())
+        };
+        let same_kind = same_unit && same_scale;
+        let same_types = beads.types().len() == self.type_index.len()
+            && beads.types().iter().enumerate().all(|(i, t)| self.type_index.get(t) == Some(&(i as u8)));
+        if same_kind && same_types && (self.type_index.len() == 1 || self.compute_flag_info().0 == 0) {
+            self.append_encoded(beads.data(), count, tag_cursor, data_cursor);
+            return Ok(());
+        }
+        let saved = (self.count, self.flag_count, self.flag_pointer, self.data_pointer);
+        for (index, bead) in beads.iter().enumerate() {
+            let pushed = if same_kind { Ok(self.push_bead(&bead)) } else { self.push_converted(&bead, beads.scale(), beads.time()) };
+            let error = match pushed {
+                Ok(true) => continue,
+                Ok(false) => BeadsError::UnsupportedValue(bead.describe()),
+                Err(e) => e
+            };
+            self.truncate(saved);
+            return Err(BeadsError::InvalidElement { index, error: Box::new(error) });
+        }
+        Ok(())
+    }
+
+    /// Pushes an int or uint `bead` of a sequence with the given scale and time kind,
+    /// converted to the scale or unit of the builder.
+    fn push_converted(&mut self, bead: &BeadReference, scale: Option<u8>, time: Option<TimeKind>) -> Result<bool, BeadsError> {
+        if !bead.is_int() && !bead.is_uint() {
+            return Ok(self.push_bead(bead));
+        }
+        if let Some(scale) = scale {
+            return Ok(self.push_decimal(bead.try_to_decimal(scale)?));
+        }
+        match time {
+            Some(TimeKind::Timestamp(unit)) => Ok(self.push_timestamp(bead.try_to_timestamp(unit)?)),
+            Some(TimeKind::Duration(unit)) => Ok(self.push_duration(bead.try_to_duration(unit)?)),
+            None => Ok(self.push_bead(bead))
+        }
+    }
+
+    /// Copies the data section of a sequence with the same types, starting at a new tag byte.
+    fn append_encoded(&mut self, data: &[u8], count: usize, tag_cursor: usize, data_cursor: usize) {
+        if count == 0 {
+            return;
+        }
+        let (base, end) = if self.type_index.len() == 1 {
+            (self.data_pointer, data_cursor)
+        } else if self.count == 0 {
+            (0, max(data_cursor, tag_cursor + 1))
+        } else {
+            (max(self.flag_pointer + 1, self.data_pointer), max(data_cursor, tag_cursor + 1))
+        };
+        self.grow_buffer_if_needed(base, end);
+        self.buffer[base..base + end].copy_from_slice(&data[..end]);
+        if self.type_index.len() > 1 {
+            self.flag_pointer = base + tag_cursor;
+        }
+        if data_cursor > 0 {
+            self.data_pointer = base + data_cursor;
+        }
+        self.count += count;
+        self.flag_count = self.count;
+        // bits of the current tag byte which are not used yet could be set by another encoder
+        if self.compute_flag_info().0 != 0 {
+            self.reset_flag();
+        }
+    }
+
+    /// Drops the elements pushed after `saved` was taken from the count and the pointers.
+    fn truncate(&mut self, (count, flag_count, flag_pointer, data_pointer): (usize, usize, usize, usize)) {
+        let end = max(self.data_pointer, self.flag_pointer + 1);
+        let kept = if self.type_index.len() == 1 { data_pointer } else { max(data_pointer, flag_pointer + 1) };
+        for b in self.buffer[kept..end].iter_mut() {
+            *b = 0;
+        }
+        self.count = count;
+        self.flag_count = flag_count;
+        self.flag_pointer = flag_pointer;
+        self.data_pointer = data_pointer;
+        if self.count == 0 || self.compute_flag_info().0 != 0 {
+            self.reset_flag();
+        }
+    }
+
+    pub fn encode<W>(&self, writer: &mut W) -> io::Result<usize> where W: io::Write {
+        <dyn BeadsBuilder>::encode(self, RefCell::new(writer).borrow_mut())
+    }
+
+    pub fn encode_with_types<W>(&self, writer: &mut W) -> io::Result<usize> where W: io::Write {
+        writer.write_all(self.type_set().bytes().as_ref())?;
+        Ok(4 + self.encode(writer)?)
+    }
+
+    /// The types of the builder together with its time kind and scale, as written by `encode_with_types`.
+    fn type_set(&self) -> BeadTypeSet {
+        let keys: Vec<BeadType> = self.type_index.keys().copied().collect();
+        let mut type_set = BeadTypeSet::new(keys.as_slice());
+        if let Some(kind) = self.time {
+            type_set = type_set.with_time(kind);
+        }
+        if let Some(scale) = self.scale {
+            // the scale was read from a type set, so it is valid
+            type_set = type_set.with_scale(scale).unwrap_or(type_set);
+        }
+        type_set
+    }
+
+    /// Bytes at the front of the buffer which will not change anymore.
+    /// With several types these are the tag groups before the current tag byte.
+    fn completed_len(&self) -> usize {
+        if self.type_index.len() == 1 {
+            self.data_pointer
+        } else {
+            self.flag_pointer
+        }
     }
 
-    pub fn encode_with_types<W>(&self, writer: &mut W) where W: io::Write {
-        let keys: Vec<BeadType> = self.type_index.keys().map(|k| k.clone()).collect();
-        let type_set = BeadTypeSet::new(keys.as_slice());
+    /// Writes the completed bytes and drops them from the buffer. The builder keeps its count,
+    /// but `encode` can't be used afterwards, as the flushed bytes are gone.
+    fn flush_completed(&mut self, writer: &mut dyn io::Write) -> io::Result<usize> {
+        let completed = self.completed_len();
+        if completed == 0 {
+            return Ok(0);
+        }
+        writer.write_all(&self.buffer[..completed])?;
+        let end = max(self.data_pointer, self.flag_pointer + 1);
+        self.buffer.copy_within(completed..end, 0);
+        // tags are or-ed into the buffer, so everything behind the kept bytes has to be zero again
+        for b in self.buffer[end - completed..end].iter_mut() {
+            *b = 0;
+        }
+        if self.type_index.len() > 1 {
+            self.flag_pointer -= completed;
+        }
+        self.data_pointer = self.data_pointer.saturating_sub(completed);
+        Ok(completed)
+    }
 
-        writer.write_all(type_set.bytes().as_ref()).expect("could not write");
-        self.encode(writer);
+    /// Writes the bytes which were not flushed yet, without the leading count.
+    /// A single type with data may have flushed all its bytes already, other sequences
+    /// end like with `encode`, which writes a padding byte if there is no data.
+    fn flush_pending(&mut self, writer: &mut dyn io::Write) -> io::Result<usize> {
+        let single_type_with_data = self.type_index.len() == 1 && self.type_index.keys().all(|t| !t.has_no_data());
+        let end = if single_type_with_data && self.count > 0 {
+            self.data_pointer
+        } else {
+            max(self.data_pointer, self.flag_pointer + 1)
+        };
+        writer.write_all(&self.buffer[..end])?;
+        Ok(end)
     }
 
     fn add_flag(&mut self, flag: u8) {
@@ -217,6 +513,9 @@ impl TypedBeadsBuilder {
     }
 
     fn reset_flag(&mut self) {
+        if self.type_index.len() == 1 {
+            return;
+        }
         let (position_in_byte, shift) = self.compute_flag_info();
         let reset_mask = if position_in_byte == 0 {0} else {255u8 >> (8 - position_in_byte * shift) as u8};
         self.buffer[self.flag_pointer] &= reset_mask;
@@ -251,28 +550,406 @@ impl TypedBeadsBuilder {
     }
 }
 
+// the kind of values of a sequence, as reported by `KindMismatch`
+fn describe_kind(scale: Option<u8>, time: Option<TimeKind>) -> String {
+    match (scale, time) {
+        (Some(scale), _) => format!("decimal (scale {})", scale),
+        (None, Some(TimeKind::Timestamp(unit))) => format!("timestamp ({:?})", unit),
+        (None, Some(TimeKind::Duration(unit))) => format!("duration ({:?})", unit),
+        (None, None) => "plain".to_string()
+    }
+}
+
 impl BeadsBuilder for TypedBeadsBuilder {
-    fn encode(&self, mut writer: RefMut<dyn io::Write + '_>) {
+    fn encode(&self, mut writer: RefMut<dyn io::Write + '_>) -> io::Result<usize> {
         let mut tmp = [0; 10];
         let count_length = add_as_vlq(tmp.as_mut(), self.count as u128);
-        writer.borrow_mut().write_all(tmp[..count_length].as_ref()).expect("could not write");
+        writer.borrow_mut().write_all(tmp[..count_length].as_ref())?;
         let buffer_length = max(self.data_pointer, self.flag_pointer+1);
-        writer.borrow_mut().write_all(self.buffer[..buffer_length].as_ref()).expect("could not write");
+        writer.borrow_mut().write_all(self.buffer[..buffer_length].as_ref())?;
+        Ok(count_length + buffer_length)
     }
 
     fn len(&self) -> usize {
         let count_length = self.count.vlq_byte_size();
-        let buffer_length = max(self.data_pointer, self.flag_pointer+1) as usize;
+        let buffer_length = max(self.data_pointer, self.flag_pointer+1);
         count_length + buffer_length
     }
 }
 
+/// Builds a sequence of ints which stores the first value and the differences to the previous value,
+/// so increasing values like ids or sensor times fit into small types like `I8` or `VlqZ`.
+///
+/// With delta-of-delta the differences between successive differences are stored instead,
+/// which are zero for values with a constant step, like timestamps of regular measurements.
+/// The encoded sequence starts with one byte, `1` for deltas and `2` for delta-of-delta,
+/// followed by a typed Beads sequence, see `DeltaBeads`.
+pub struct DeltaBeadsBuilder {
+    builder: TypedBeadsBuilder,
+    delta_of_delta: bool,
+    count: usize,
+    previous: i128,
+    previous_delta: i128,
+}
+
+impl DeltaBeadsBuilder {
+    pub fn new(types: &BeadTypeSet) -> Result<DeltaBeadsBuilder, BeadsError> {
+        Ok(DeltaBeadsBuilder {
+            builder: TypedBeadsBuilder::new(types)?,
+            delta_of_delta: false,
+            count: 0,
+            previous: 0,
+            previous_delta: 0
+        })
+    }
+
+    pub fn with_delta_of_delta(types: &BeadTypeSet) -> Result<DeltaBeadsBuilder, BeadsError> {
+        let mut builder = Self::new(types)?;
+        builder.delta_of_delta = true;
+        Ok(builder)
+    }
+
+    /// Returns false if the value, or its difference, can't be stored with the types of the builder.
+    /// Differences are computed with wrapping arithmetic, so every `i128` can be stored.
+    pub fn push_int(&mut self, value: i128) -> bool {
+        let delta = value.wrapping_sub(self.previous);
+        let stored = match self.count {
+            0 => value,
+            1 => delta,
+            _ if self.delta_of_delta => delta.wrapping_sub(self.previous_delta),
+            _ => delta
+        };
+        if !self.builder.push_int(stored) {
+            return false;
+        }
+        if self.count > 0 {
+            self.previous_delta = delta;
+        }
+        self.previous = value;
+        self.count += 1;
+        true
+    }
+
+    /// Pushes `time` in the unit declared with `BeadTypeSet::with_time`.
+    /// Returns false if the builder was not created for timestamps.
+    pub fn push_timestamp(&mut self, time: SystemTime) -> bool {
+        match self.builder.time {
+            Some(TimeKind::Timestamp(unit)) => self.push_int(unit.count_since_epoch(time)),
+            _ => false
+        }
+    }
+
+    pub fn encode<W>(&self, writer: &mut W) -> io::Result<usize> where W: io::Write {
+        <dyn BeadsBuilder>::encode(self, RefCell::new(writer).borrow_mut())
+    }
+
+    /// Same as `encode`, with the type set between the first byte and the typed sequence.
+    pub fn encode_with_types<W>(&self, writer: &mut W) -> io::Result<usize> where W: io::Write {
+        writer.write_all(&[self.order()])?;
+        Ok(1 + self.builder.encode_with_types(writer)?)
+    }
+
+    fn order(&self) -> u8 {
+        if self.delta_of_delta { 2 } else { 1 }
+    }
+}
+
+impl BeadsBuilder for DeltaBeadsBuilder {
+    fn encode(&self, mut writer: RefMut<dyn io::Write + '_>) -> io::Result<usize> {
+        writer.write_all(&[self.order()])?;
+        Ok(1 + <dyn BeadsBuilder>::encode(&self.builder, writer)?)
+    }
+
+    fn len(&self) -> usize {
+        1 + <dyn BeadsBuilder>::len(&self.builder)
+    }
+}
+
+// value of the current run of a `RleBeadsBuilder`, as it was pushed
+#[derive(PartialEq)]
+enum RunValue {
+    Uint(u128),
+    Int(i128),
+    Double(u64, u64),
+    Str(String),
+    Bytes(Vec<u8>),
+    Bool(bool),
+    None,
+}
+
+/// Builds a sequence where successive equal values are stored once, together with the length of their run.
+///
+/// The encoded sequence is an `IndexedBeads` sequence of two elements: the values of the runs,
+/// encoded like by a `TypedBeadsBuilder` with the types of the builder, and the run lengths as `Vlq` values.
+/// Values are equal if they were pushed with the same method and the same arguments, see `RleBeads`.
+pub struct RleBeadsBuilder {
+    values: TypedBeadsBuilder,
+    runs: Vec<usize>,
+    // encoded size of the run lengths, without their count
+    runs_bytes: usize,
+    last: Option<RunValue>,
+}
+
+impl RleBeadsBuilder {
+    pub fn new(types: &BeadTypeSet) -> Result<RleBeadsBuilder, BeadsError> {
+        Ok(RleBeadsBuilder {
+            values: TypedBeadsBuilder::new(types)?,
+            runs: vec![],
+            runs_bytes: 0,
+            last: None
+        })
+    }
+
+    pub fn push_uint(&mut self, value: u128) -> bool {
+        self.push_run(RunValue::Uint(value), |b| b.push_uint(value))
+    }
+
+    pub fn push_int(&mut self, value: i128) -> bool {
+        self.push_run(RunValue::Int(value), |b| b.push_int(value))
+    }
+
+    pub fn push_double(&mut self, value: f64) -> bool {
+        self.push_double_with_accuracy(value, 0.0)
+    }
+
+    pub fn push_double_with_accuracy(&mut self, value: f64, accuracy: f64) -> bool {
+        self.push_run(RunValue::Double(value.to_bits(), accuracy.to_bits()), |b| b.push_double_with_accuracy(value, accuracy))
+    }
+
+    pub fn push_string(&mut self, value: &str) -> bool {
+        if matches!(&self.last, Some(RunValue::Str(last)) if last == value) {
+            return self.extend_run();
+        }
+        self.push_run(RunValue::Str(value.to_string()), |b| b.push_string(value))
+    }
+
+    pub fn push_bytes(&mut self, value: &[u8]) -> bool {
+        if matches!(&self.last, Some(RunValue::Bytes(last)) if last.as_slice() == value) {
+            return self.extend_run();
+        }
+        self.push_run(RunValue::Bytes(value.to_vec()), |b| b.push_bytes(value))
+    }
+
+    pub fn push_bool(&mut self, value: bool) -> bool {
+        self.push_run(RunValue::Bool(value), |b| b.push_bool(value))
+    }
+
+    pub fn push_none(&mut self) -> bool {
+        self.push_run(RunValue::None, |b| b.push_none())
+    }
+
+    /// Number of runs, which is the number of stored values.
+    pub fn runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    pub fn encode<W>(&self, writer: &mut W) -> io::Result<usize> where W: io::Write {
+        <dyn BeadsBuilder>::encode(self, RefCell::new(writer).borrow_mut())
+    }
+
+    /// Same as `encode`, with the values written by `TypedBeadsBuilder::encode_with_types`.
+    pub fn encode_with_types<W>(&self, writer: &mut W) -> io::Result<usize> where W: io::Write {
+        let mut values = vec![];
+        self.values.encode_with_types(&mut values)?;
+        self.encode_parts(&values, writer)
+    }
+
+    fn push_run<F>(&mut self, value: RunValue, push: F) -> bool where F: FnOnce(&mut TypedBeadsBuilder) -> bool {
+        if self.last.as_ref() == Some(&value) {
+            return self.extend_run();
+        }
+        if !push(&mut self.values) {
+            return false;
+        }
+        self.last = Some(value);
+        self.runs.push(1);
+        self.runs_bytes += 1;
+        true
+    }
+
+    fn extend_run(&mut self) -> bool {
+        if let Some(run) = self.runs.last_mut() {
+            self.runs_bytes -= run.vlq_byte_size();
+            *run += 1;
+            self.runs_bytes += run.vlq_byte_size();
+        }
+        true
+    }
+
+    fn encode_parts<W>(&self, values: &[u8], writer: &mut W) -> io::Result<usize> where W: io::Write + ?Sized {
+        let mut lengths = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Vlq])).map_err(|e| io::Error::other(e.to_string()))?;
+        for run in self.runs.iter() {
+            lengths.push_uint(*run as u128);
+        }
+        let mut lengths_buffer = vec![];
+        lengths.encode(&mut lengths_buffer)?;
+        let mut indexed = IndexedBeadsBuilder::new();
+        indexed.push(values);
+        indexed.push(&lengths_buffer);
+        <dyn BeadsBuilder>::encode(&indexed, RefCell::new(writer).borrow_mut())
+    }
+}
+
+impl BeadsBuilder for RleBeadsBuilder {
+    fn encode(&self, mut writer: RefMut<dyn io::Write + '_>) -> io::Result<usize> {
+        let mut values = vec![];
+        self.values.encode(&mut values)?;
+        self.encode_parts(&values, &mut *writer)
+    }
+
+    fn len(&self) -> usize {
+        // the run lengths are encoded with the single type `Vlq`, which takes at least one byte
+        let lengths_length = self.runs.len().vlq_byte_size() + max(self.runs_bytes, 1);
+        let cursor = self.values.len() + lengths_length;
+        let bytes_per_index_entry = (8 - (cursor as u64).leading_zeros() / 8) as usize;
+        // the header of two elements always fits into one byte
+        1 + 2 * bytes_per_index_entry + cursor
+    }
+}
+
+const STREAM_COUNT_SIZE: usize = 10;
+const STREAM_FLUSH_SIZE: usize = 4096;
+
+/// Builds a typed Beads sequence directly into a seekable writer.
+///
+/// Completed tag groups are written as soon as there are enough of them, so the memory use
+/// does not depend on the number of pushed values. The count is written as a padded 10 byte
+/// VLQ placeholder and patched in `finish`, the rest of the output is the same as from
+/// `TypedBeadsBuilder::encode`.
+///
+/// A builder which is dropped without calling `finish` leaves the placeholder zeroed. Such output
+/// reads as a count of 0 followed by bytes which don't belong to the sequence.
+pub struct StreamingTypedBeadsBuilder<W> where W: io::Write + io::Seek {
+    builder: TypedBeadsBuilder,
+    writer: W,
+    count_position: u64,
+    written: usize,
+}
+
+impl<W> StreamingTypedBeadsBuilder<W> where W: io::Write + io::Seek {
+    pub fn new(types: &BeadTypeSet, mut writer: W) -> Result<StreamingTypedBeadsBuilder<W>, BeadsError> {
+        let builder = TypedBeadsBuilder::new(types)?;
+        let count_position = writer.stream_position()?;
+        writer.write_all(&[0; STREAM_COUNT_SIZE])?;
+        Ok(StreamingTypedBeadsBuilder {
+            builder,
+            writer,
+            count_position,
+            written: STREAM_COUNT_SIZE
+        })
+    }
+
+    /// Same as `new`, but starts with the type set, like `TypedBeadsBuilder::encode_with_types`.
+    pub fn new_with_types(types: &BeadTypeSet, mut writer: W) -> Result<StreamingTypedBeadsBuilder<W>, BeadsError> {
+        let builder = TypedBeadsBuilder::new(types)?;
+        writer.write_all(builder.type_set().bytes().as_ref())?;
+        let mut result = StreamingTypedBeadsBuilder::new(types, writer)?;
+        result.written += 4;
+        Ok(result)
+    }
+
+    pub fn push_bool(&mut self, value: bool) -> io::Result<bool> {
+        let added = self.builder.push_bool(value);
+        self.flush_if_needed(added)
+    }
+
+    pub fn push_none(&mut self) -> io::Result<bool> {
+        let added = self.builder.push_none();
+        self.flush_if_needed(added)
+    }
+
+    pub fn push_string(&mut self, value: &str) -> io::Result<bool> {
+        let added = self.builder.push_string(value);
+        self.flush_if_needed(added)
+    }
+
+    pub fn push_bytes(&mut self, value: &[u8]) -> io::Result<bool> {
+        let added = self.builder.push_bytes(value);
+        self.flush_if_needed(added)
+    }
+
+    pub fn push_uint(&mut self, value: u128) -> io::Result<bool> {
+        let added = self.builder.push_uint(value);
+        self.flush_if_needed(added)
+    }
+
+    pub fn push_int(&mut self, value: i128) -> io::Result<bool> {
+        let added = self.builder.push_int(value);
+        self.flush_if_needed(added)
+    }
+
+    pub fn push_double(&mut self, value: f64) -> io::Result<bool> {
+        let added = self.builder.push_double(value);
+        self.flush_if_needed(added)
+    }
+
+    pub fn push_beads(&mut self, beads: &dyn BeadsBuilder) -> io::Result<bool> {
+        let added = self.builder.push_beads(beads);
+        self.flush_if_needed(added)
+    }
+
+    pub fn push_decimal(&mut self, value: Decimal) -> io::Result<bool> {
+        let added = self.builder.push_decimal(value);
+        self.flush_if_needed(added)
+    }
+
+    pub fn push_timestamp(&mut self, time: SystemTime) -> io::Result<bool> {
+        let added = self.builder.push_timestamp(time);
+        self.flush_if_needed(added)
+    }
+
+    pub fn push_duration(&mut self, duration: Duration) -> io::Result<bool> {
+        let added = self.builder.push_duration(duration);
+        self.flush_if_needed(added)
+    }
+
+    pub fn push_double_with_accuracy(&mut self, value: f64, accuracy: f64) -> io::Result<bool> {
+        let added = self.builder.push_double_with_accuracy(value, accuracy);
+        self.flush_if_needed(added)
+    }
+
+    /// Number of pushed values.
+    pub fn len(&self) -> usize {
+        self.builder.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes the pending bytes, patches the count and returns the writer together with the
+    /// number of bytes written. The writer is left positioned at the end of the sequence.
+    pub fn finish(mut self) -> io::Result<(W, usize)> {
+        self.written += self.builder.flush_pending(&mut self.writer)?;
+        let end = self.writer.stream_position()?;
+        let mut count = [0; STREAM_COUNT_SIZE];
+        add_as_padded_vlq(&mut count, self.builder.count as u128);
+        self.writer.seek(io::SeekFrom::Start(self.count_position))?;
+        self.writer.write_all(&count)?;
+        self.writer.seek(io::SeekFrom::Start(end))?;
+        Ok((self.writer, self.written))
+    }
+
+    fn flush_if_needed(&mut self, added: bool) -> io::Result<bool> {
+        if added && self.builder.completed_len() >= STREAM_FLUSH_SIZE {
+            self.written += self.builder.flush_completed(&mut self.writer)?;
+        }
+        Ok(added)
+    }
+}
+
 pub struct IndexedBeadsBuilder <'a> {
     indexes: Vec<u8>,
     buffers: Vec<&'a[u8]>,
     cursor: u64
 }
 
+impl <'a> Default for IndexedBeadsBuilder<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl <'a> IndexedBeadsBuilder <'a>{
     pub fn new() -> IndexedBeadsBuilder<'a> {
         IndexedBeadsBuilder {
@@ -290,13 +967,13 @@ impl <'a> IndexedBeadsBuilder <'a>{
         self.indexes.append(size_as_buf.as_mut());
     }
 
-    pub fn encode<W>(&self, writer: &mut W) where W: io::Write {
-        <dyn BeadsBuilder>::encode(self, RefCell::new(writer).borrow_mut());
+    pub fn encode<W>(&self, writer: &mut W) -> io::Result<usize> where W: io::Write {
+        <dyn BeadsBuilder>::encode(self, RefCell::new(writer).borrow_mut())
     }
 
-    pub fn encode_from_beads_builders<W>(writer: &mut W, builders: Vec<Box<dyn BeadsBuilder + '_>>) where W: io::Write {
+    pub fn encode_from_beads_builders<W>(writer: &mut W, builders: Vec<Box<dyn BeadsBuilder + '_>>) -> io::Result<usize> where W: io::Write {
         if builders.is_empty() {
-            return
+            return Ok(0)
         }
         let cursor: usize = builders.iter().map(|b|b.len()).sum();
         let bytes_per_index_entry = (8 - cursor.leading_zeros() / 8) as usize;
@@ -305,24 +982,27 @@ impl <'a> IndexedBeadsBuilder <'a>{
 
         let mut tmp = [0; 19];
         let count_length = add_as_vlq(tmp.as_mut(), header);
-        writer.write_all(tmp[..count_length].as_ref()).expect("could not write");
+        writer.write_all(tmp[..count_length].as_ref())?;
+        let mut written = count_length;
         let mut cursor = 0;
         for b in builders.iter() {
             cursor += b.len();
             let bytes = cursor.to_le_bytes();
-            writer.write_all(&bytes[..bytes_per_index_entry]).expect("could not write");
+            writer.write_all(&bytes[..bytes_per_index_entry])?;
+            written += bytes_per_index_entry;
         }
         let writer_cell = RefCell::new(writer);
         for b in builders.iter() {
-            b.encode(writer_cell.borrow_mut());
+            written += b.encode(writer_cell.borrow_mut())?;
         }
+        Ok(written)
     }
 }
 
 impl BeadsBuilder for IndexedBeadsBuilder<'_> {
-    fn encode(&self, mut writer: RefMut<dyn io::Write + '_>) {
+    fn encode(&self, mut writer: RefMut<dyn io::Write + '_>) -> io::Result<usize> {
         if self.cursor == 0 {
-            return
+            return Ok(0)
         }
 
         let bytes_per_index_entry = (8 - self.cursor.leading_zeros() / 8) as usize;
@@ -331,19 +1011,27 @@ impl BeadsBuilder for IndexedBeadsBuilder<'_> {
 
         let mut tmp = [0; 19];
         let count_length = add_as_vlq(tmp.as_mut(), header);
-        writer.borrow_mut().write_all(tmp[..count_length].as_ref()).expect("could not write");
+        writer.borrow_mut().write_all(tmp[..count_length].as_ref())?;
+        let mut written = count_length;
         for i in (0..self.indexes.len()).step_by(8) {
-            writer.borrow_mut().write_all(&self.indexes[i..i+bytes_per_index_entry]).expect("could not write");
+            writer.borrow_mut().write_all(&self.indexes[i..i+bytes_per_index_entry])?;
+            written += bytes_per_index_entry;
         }
         for b in self.buffers.iter() {
-            writer.borrow_mut().write_all(b).expect("could not write");
+            writer.borrow_mut().write_all(b)?;
+            written += b.len();
         }
+        Ok(written)
     }
 
     fn len(&self) -> usize {
+        // like `encode`, which writes nothing without values
+        if self.cursor == 0 {
+            return 0;
+        }
         let bytes_per_index_entry = (8 - self.cursor.leading_zeros() / 8) as usize;
         let count_length = (self.buffers.len() << 3).vlq_byte_size();
-        let index_bytes = ((self.indexes.len() / 8) * bytes_per_index_entry) as usize;
+        let index_bytes = (self.indexes.len() / 8) * bytes_per_index_entry;
         let values_bytes: usize = self.buffers.iter().map(|b| b.len()).sum();
         count_length + index_bytes + values_bytes
     }
@@ -364,22 +1052,23 @@ impl FixedSizeBeadsBuilder {
 
     pub fn push(&mut self, value: &[u8]) {
         if value.len() != self.size {
-            panic!(format!("Value {:?} is not of fix size {}", value, self.size));
+            panic!("Value {:?} is not of fix size {}", value, self.size);
         }
         self.buffer.append(value.to_vec().as_mut());
     }
 
-    pub fn encode<W>(&self, writer: &mut W) where W: io::Write {
-        <dyn BeadsBuilder>::encode(self, RefCell::new(writer).borrow_mut());
+    pub fn encode<W>(&self, writer: &mut W) -> io::Result<usize> where W: io::Write {
+        <dyn BeadsBuilder>::encode(self, RefCell::new(writer).borrow_mut())
     }
 }
 
 impl BeadsBuilder for FixedSizeBeadsBuilder {
-    fn encode(&self, mut writer: RefMut<dyn io::Write + '_>) {
+    fn encode(&self, mut writer: RefMut<dyn io::Write + '_>) -> io::Result<usize> {
         let mut tmp = [0; 10];
         let count_length = add_as_vlq(tmp.as_mut(), self.size as u128);
-        writer.borrow_mut().write_all(tmp[..count_length].as_ref()).expect("could not write");
-        writer.borrow_mut().write_all(self.buffer.as_slice()).expect("could not write");
+        writer.borrow_mut().write_all(tmp[..count_length].as_ref())?;
+        writer.borrow_mut().write_all(self.buffer.as_slice())?;
+        Ok(count_length + self.buffer.len())
     }
 
     fn len(&self) -> usize {
@@ -393,6 +1082,12 @@ pub struct FixedSizeBeadsIncrementalUintBuilder {
     buffer: Vec<u8>
 }
 
+impl Default for FixedSizeBeadsIncrementalUintBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FixedSizeBeadsIncrementalUintBuilder {
     pub fn new() -> FixedSizeBeadsIncrementalUintBuilder {
         FixedSizeBeadsIncrementalUintBuilder {
@@ -402,28 +1097,115 @@ impl FixedSizeBeadsIncrementalUintBuilder {
     }
 
     pub fn push(&mut self, value: u128) {
-        let bytes_per_entry = (16 - value.leading_zeros() / 8) as usize;
+        // zero still needs one byte, otherwise the number of entries would get lost
+        let bytes_per_entry = max((16 - value.leading_zeros() / 8) as usize, 1);
         self.size = max(self.size, bytes_per_entry);
         self.buffer.append(value.to_le_bytes().to_vec().as_mut());
     }
 
-    pub fn encode<W>(&self, writer: &mut W) where W: io::Write {
-        <dyn BeadsBuilder>::encode(self, RefCell::new(writer).borrow_mut());
+    pub fn encode<W>(&self, writer: &mut W) -> io::Result<usize> where W: io::Write {
+        <dyn BeadsBuilder>::encode(self, RefCell::new(writer).borrow_mut())
     }
 }
 
 impl BeadsBuilder for FixedSizeBeadsIncrementalUintBuilder {
-    fn encode(&self, mut writer: RefMut<dyn io::Write + '_>) {
+    fn encode(&self, mut writer: RefMut<dyn io::Write + '_>) -> io::Result<usize> {
         let mut tmp = [0; 10];
         let b_writer = writer.borrow_mut();
         let count_length = add_as_vlq(tmp.as_mut(), self.size as u128);
-        b_writer.write_all(tmp[..count_length].as_ref()).expect("could not write");
+        b_writer.write_all(tmp[..count_length].as_ref())?;
         for i in (0..self.buffer.len()).step_by(16) {
-            b_writer.write_all(&self.buffer[i..i+self.size]).expect("could not write");
+            b_writer.write_all(&self.buffer[i..i+self.size])?;
         }
+        Ok(count_length + (self.buffer.len() / 16) * self.size)
     }
     fn len(&self) -> usize {
         let count_length = self.size.vlq_byte_size();
         count_length + (self.buffer.len() / 16) * self.size
     }
-}
\ No newline at end of file
+}
+
+/// Builds a sequence of uints which stores the smallest value once and every value as the difference to it,
+/// with as many bits as the largest difference needs. For example 300 dictionary codes take 9 bits each,
+/// where `FixedSizeBeadsIncrementalUintBuilder` would use 2 bytes.
+///
+/// The encoded sequence starts with the number of values and the smallest value as VLQ,
+/// followed by one byte with the number of bits per value and the bits of all values, lowest bit first.
+pub struct BitPackedBeadsBuilder {
+    values: Vec<u128>,
+    min: u128,
+    max: u128,
+}
+
+impl Default for BitPackedBeadsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BitPackedBeadsBuilder {
+    pub fn new() -> BitPackedBeadsBuilder {
+        BitPackedBeadsBuilder {
+            values: vec![],
+            min: u128::MAX,
+            max: 0
+        }
+    }
+
+    pub fn push(&mut self, value: u128) {
+        self.min = std::cmp::min(self.min, value);
+        self.max = max(self.max, value);
+        self.values.push(value);
+    }
+
+    /// Number of bits per value, which is 0 if all values are the same.
+    pub fn bits(&self) -> usize {
+        if self.values.is_empty() {
+            return 0;
+        }
+        (128 - (self.max - self.min).leading_zeros()) as usize
+    }
+
+    pub fn encode<W>(&self, writer: &mut W) -> io::Result<usize> where W: io::Write {
+        <dyn BeadsBuilder>::encode(self, RefCell::new(writer).borrow_mut())
+    }
+
+    fn reference(&self) -> u128 {
+        if self.values.is_empty() { 0 } else { self.min }
+    }
+}
+
+impl BeadsBuilder for BitPackedBeadsBuilder {
+    fn encode(&self, mut writer: RefMut<dyn io::Write + '_>) -> io::Result<usize> {
+        let mut tmp = [0; 38];
+        let mut header_length = add_as_vlq(tmp.as_mut(), self.values.len() as u128);
+        header_length += add_as_vlq(tmp[header_length..].as_mut(), self.reference());
+        let bits = self.bits();
+        tmp[header_length] = bits as u8;
+        header_length += 1;
+        writer.write_all(&tmp[..header_length])?;
+
+        let mut packed = vec![0u8; (self.values.len() * bits).div_ceil(8)];
+        for (index, value) in self.values.iter().enumerate() {
+            let mut delta = value - self.min;
+            let mut bit = index * bits;
+            let mut left = bits;
+            while left > 0 {
+                let offset = bit % 8;
+                let taken = std::cmp::min(8 - offset, left);
+                packed[bit / 8] |= ((delta & ((1 << taken) - 1)) as u8) << offset;
+                delta >>= taken;
+                bit += taken;
+                left -= taken;
+            }
+        }
+        writer.write_all(&packed)?;
+        Ok(header_length + packed.len())
+    }
+
+    fn len(&self) -> usize {
+        let mut tmp = [0; 19];
+        let reference_length = add_as_vlq(tmp.as_mut(), self.reference());
+        self.values.len().vlq_byte_size() + reference_length + 1 + (self.values.len() * self.bits()).div_ceil(8)
+    }
+}
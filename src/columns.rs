@@ -0,0 +1,145 @@
+use std::convert::TryFrom;
+use std::io;
+use std::fmt;
+use crate::bead_type::BeadType;
+use crate::builder::TypedBeadsBuilder;
+use crate::error::BeadsError;
+use crate::reference::BeadReference;
+use crate::iterator::BeadsIterator;
+
+/// Rows which are stored as one Beads sequence per field, combined into an `IndexedBeads` buffer.
+/// Usually implemented with `#[derive(BeadsColumns)]` from the `rusty_beads_derive` crate.
+pub trait BeadsColumns: Sized {
+    fn encode_columns<W>(rows: &[Self], writer: &mut W) -> Result<usize, BeadsError> where W: io::Write;
+    fn decode_columns(buffer: &[u8]) -> Result<Vec<Self>, BeadsError>;
+}
+
+/// A field type which can be stored in a column.
+pub trait ColumnValue: Sized {
+    /// Types used for the column, when the field has no `#[beads(...)]` attribute.
+    fn default_types() -> Vec<BeadType>;
+    fn push_to(&self, builder: &mut TypedBeadsBuilder) -> bool;
+    fn from_bead(bead: BeadReference) -> Result<Self, BeadsError>;
+}
+
+macro_rules! column_value_uint {
+    ( $( $x:ident: $( $t:ident )|* );* ) => {
+        $(
+impl ColumnValue for $x {
+    fn default_types() -> Vec<BeadType> { vec![$( BeadType::$t ),*] }
+    fn push_to(&self, builder: &mut TypedBeadsBuilder) -> bool { builder.push_uint(*self as u128) }
+    fn from_bead(bead: BeadReference) -> Result<Self, BeadsError> { $x::try_from(bead) }
+}
+        )*
+    }
+}
+
+macro_rules! column_value_int {
+    ( $( $x:ident: $( $t:ident )|* );* ) => {
+        $(
+impl ColumnValue for $x {
+    fn default_types() -> Vec<BeadType> { vec![$( BeadType::$t ),*] }
+    fn push_to(&self, builder: &mut TypedBeadsBuilder) -> bool { builder.push_int(*self as i128) }
+    fn from_bead(bead: BeadReference) -> Result<Self, BeadsError> { $x::try_from(bead) }
+}
+        )*
+    }
+}
+
+column_value_uint![
+    u8: U8;
+    u16: U8 | U16;
+    u32: U8 | U16 | U32;
+    u64: Vlq;
+    u128: Vlq;
+    usize: Vlq
+];
+
+column_value_int![
+    i8: I8;
+    i16: I8 | I16;
+    i32: I8 | I16 | I32;
+    i64: VlqZ;
+    i128: VlqZ;
+    isize: VlqZ
+];
+
+impl ColumnValue for f32 {
+    fn default_types() -> Vec<BeadType> { vec![BeadType::F32] }
+    fn push_to(&self, builder: &mut TypedBeadsBuilder) -> bool { builder.push_double(*self as f64) }
+    fn from_bead(bead: BeadReference) -> Result<Self, BeadsError> { f32::try_from(bead) }
+}
+
+impl ColumnValue for f64 {
+    fn default_types() -> Vec<BeadType> { vec![BeadType::F32, BeadType::F64] }
+    fn push_to(&self, builder: &mut TypedBeadsBuilder) -> bool { builder.push_double(*self) }
+    fn from_bead(bead: BeadReference) -> Result<Self, BeadsError> { f64::try_from(bead) }
+}
+
+impl ColumnValue for bool {
+    fn default_types() -> Vec<BeadType> { vec![BeadType::TrueFlag, BeadType::FalseFlag] }
+    fn push_to(&self, builder: &mut TypedBeadsBuilder) -> bool { builder.push_bool(*self) }
+    fn from_bead(bead: BeadReference) -> Result<Self, BeadsError> { bead.try_to_bool() }
+}
+
+impl ColumnValue for String {
+    fn default_types() -> Vec<BeadType> { vec![BeadType::Utf8] }
+    fn push_to(&self, builder: &mut TypedBeadsBuilder) -> bool { builder.push_string(self) }
+    fn from_bead(bead: BeadReference) -> Result<Self, BeadsError> { String::try_from(bead) }
+}
+
+impl ColumnValue for Vec<u8> {
+    fn default_types() -> Vec<BeadType> { vec![BeadType::Bytes] }
+    fn push_to(&self, builder: &mut TypedBeadsBuilder) -> bool { builder.push_bytes(self) }
+    fn from_bead(bead: BeadReference) -> Result<Self, BeadsError> {
+        if bead.is_bytes() || bead.is_string() {
+            Ok(bead.to_bytes().to_vec())
+        } else {
+            Err(BeadsError::TypeMismatch { found: bead.bead_type, expected: "bytes" })
+        }
+    }
+}
+
+impl<T> ColumnValue for Option<T> where T: ColumnValue {
+    fn default_types() -> Vec<BeadType> {
+        let mut types = T::default_types();
+        types.push(BeadType::None);
+        types
+    }
+    fn push_to(&self, builder: &mut TypedBeadsBuilder) -> bool {
+        match self {
+            Some(v) => v.push_to(builder),
+            None => builder.push_none()
+        }
+    }
+    fn from_bead(bead: BeadReference) -> Result<Self, BeadsError> {
+        if bead.is_none() {
+            Ok(None)
+        } else {
+            T::from_bead(bead).map(Some)
+        }
+    }
+}
+
+/// Pushes the `field` value of row number `row`, used by the derived `encode_columns`.
+pub fn push_value<T>(value: &T, builder: &mut TypedBeadsBuilder, field: &'static str, row: usize) -> Result<(), BeadsError> where T: ColumnValue + fmt::Debug {
+    if !value.push_to(builder) {
+        let error = BeadsError::UnsupportedValue(format!("{:?}", value));
+        return Err(BeadsError::InvalidField { field, row, error: Box::new(error) });
+    }
+    Ok(())
+}
+
+/// Reads the value of row number `row` from a column, used by the derived `decode_columns`.
+pub fn next_value<T>(column: &mut BeadsIterator, row: usize, len: usize) -> Result<T, BeadsError> where T: ColumnValue {
+    let bead = column.try_next().ok_or(BeadsError::IndexOutOfBounds { index: row, len })??;
+    T::from_bead(bead)
+}
+
+/// Fails with `ColumnLengthMismatch` if `column` does not hold `rows` elements.
+pub fn check_column_len(column: usize, len: usize, rows: usize) -> Result<(), BeadsError> {
+    if len != rows {
+        return Err(BeadsError::ColumnLengthMismatch { column, len, rows });
+    }
+    Ok(())
+}
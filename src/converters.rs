@@ -1,10 +1,12 @@
 use std::io;
-use crate::builder::{TypedBeadsBuilder, IndexedBeadsBuilder, FixedSizeBeadsIncrementalUintBuilder, BeadsBuilder};
+use crate::builder::{TypedBeadsBuilder, IndexedBeadsBuilder, FixedSizeBeadsIncrementalUintBuilder, BitPackedBeadsBuilder, BeadsBuilder};
 use crate::bead_type::{BeadTypeSet, BeadType};
 use crate::sequence::TypedBeads;
 use std::collections::{HashMap};
+use crate::error::BeadsError;
+use crate::decimal::Decimal;
 
-pub fn csv_to_indexed_string_beads<W>(csv: &str, writer: &mut W) -> Result<(), &'static str> where W: io::Write {
+pub fn csv_to_indexed_string_beads<W>(csv: &str, writer: &mut W) -> Result<(), BeadsError> where W: io::Write {
     let csv = csv.as_bytes();
     let mut offset = 0;
     let quote = "\"".as_bytes()[0];
@@ -14,14 +16,14 @@ pub fn csv_to_indexed_string_beads<W>(csv: &str, writer: &mut W) -> Result<(), &
     let mut is_in_double_quotes = false;
     let mut bytes: Vec<u8> = vec![];
     let mut column_index = 0;
-    let mut builders: Vec<Box<TypedBeadsBuilder>> = vec![];
+    let mut builders: Vec<TypedBeadsBuilder> = vec![];
 
-    fn add_bytes(builders: &mut Vec<Box<TypedBeadsBuilder>>, bytes: &mut Vec<u8>, column_index: &mut usize) -> Result<(), &'static str> {
+    fn add_bytes(builders: &mut Vec<TypedBeadsBuilder>, bytes: &mut Vec<u8>, column_index: &mut usize) -> Result<(), BeadsError> {
         if builders.len() <= *column_index {
-            builders.push(Box::new(TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Utf8]))?));
+            builders.push(TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Utf8]))?);
         }
         let builder = &mut builders[*column_index];
-        builder.push_string(std::str::from_utf8(&bytes).unwrap());
+        builder.push_string(std::str::from_utf8(bytes).unwrap());
         bytes.clear();
         Ok(())
     }
@@ -29,7 +31,7 @@ pub fn csv_to_indexed_string_beads<W>(csv: &str, writer: &mut W) -> Result<(), &
     while offset < csv.len() {
         let char = csv[offset];
         if char == quote {
-            if is_in_double_quotes == false {
+            if !is_in_double_quotes {
                 is_in_double_quotes = true;
                 offset += 1;
             } else {
@@ -41,19 +43,19 @@ pub fn csv_to_indexed_string_beads<W>(csv: &str, writer: &mut W) -> Result<(), &
                     offset += 1;
                 }
             }
-        } else if char == separator && is_in_double_quotes == false {
+        } else if char == separator && !is_in_double_quotes {
 
             add_bytes(&mut builders, &mut bytes, &mut column_index)?;
 
             offset += 1;
             column_index += 1;
-        } else if char == n && is_in_double_quotes == false {
+        } else if char == n && !is_in_double_quotes {
 
             add_bytes(&mut builders, &mut bytes, &mut column_index)?;
 
             offset += 1;
             column_index = 0;
-        } else if char == r && csv.len() > offset + 1 && csv[offset+1] == n && is_in_double_quotes == false {
+        } else if char == r && csv.len() > offset + 1 && csv[offset+1] == n && !is_in_double_quotes {
 
             add_bytes(&mut builders, &mut bytes, &mut column_index)?;
 
@@ -65,103 +67,159 @@ pub fn csv_to_indexed_string_beads<W>(csv: &str, writer: &mut W) -> Result<(), &
         }
     }
 
-    if bytes.is_empty() == false {
+    if !bytes.is_empty() {
         add_bytes(&mut builders, &mut bytes, &mut column_index)?;
     }
 
     let mut boxed_builders: Vec<Box<dyn BeadsBuilder>> = vec![];
     for b in builders {
-        boxed_builders.push(b);
+        boxed_builders.push(Box::new(b));
     }
 
-    IndexedBeadsBuilder::encode_from_beads_builders(writer, boxed_builders);
+    IndexedBeadsBuilder::encode_from_beads_builders(writer, boxed_builders)?;
     Ok(())
 }
 
-pub fn string_beads_to_int_beads<W>(buffer: &[u8], type_set: &BeadTypeSet, writer: &mut W)  -> Result<(), String> where W: io::Write {
+pub fn string_beads_to_int_beads<W>(buffer: &[u8], type_set: &BeadTypeSet, writer: &mut W)  -> Result<(), BeadsError> where W: io::Write {
     let string_beads = TypedBeads::new(buffer, &BeadTypeSet::new(&[BeadType::Utf8]))?;
     let mut builder = TypedBeadsBuilder::new(type_set)?;
-    for sb in string_beads.iter() {
-        let s = sb.to_str();
+    for (index, sb) in string_beads.iter().enumerate() {
+        let s = sb.try_to_str().map_err(|e| BeadsError::InvalidElement { index, error: Box::new(e) })?;
         let v = match s.parse::<i128>() {
             Ok(v) => v,
             Err(_) => {
-                if builder.push_none() == false {
-                    return Err(format!("Could not parse value {}", s).to_string());
+                if !builder.push_none() {
+                    return Err(BeadsError::UnparsableValue(s.to_string()));
                 } else {
                     continue;
                 }
             }
         };
-        if builder.push_int(v) == false {
-            if builder.push_none() == false {
-                return Err(format!("Could not push value {}", v).to_string())
-            }
+        if !builder.push_int(v) && !builder.push_none() {
+            return Err(BeadsError::UnsupportedValue(v.to_string()))
         }
     }
-    builder.encode(writer);
+    builder.encode(writer)?;
     Ok(())
 }
 
-pub fn string_beads_to_double_beads<W>(buffer: &[u8], type_set: &BeadTypeSet, accuracy: f64, writer: &mut W)  -> Result<(), String> where W: io::Write {
+pub fn string_beads_to_double_beads<W>(buffer: &[u8], type_set: &BeadTypeSet, accuracy: f64, writer: &mut W)  -> Result<(), BeadsError> where W: io::Write {
     let string_beads = TypedBeads::new(buffer, &BeadTypeSet::new(&[BeadType::Utf8]))?;
     let mut builder = TypedBeadsBuilder::new(type_set)?;
-    for sb in string_beads.iter() {
-        let s = sb.to_str();
+    for (index, sb) in string_beads.iter().enumerate() {
+        let s = sb.try_to_str().map_err(|e| BeadsError::InvalidElement { index, error: Box::new(e) })?;
         let v = match s.parse::<f64>() {
             Ok(v) => v,
             Err(_) => {
-                if builder.push_none() == false {
-                    return Err(format!("Could not parse value '{}'", s).to_string());
+                if !builder.push_none() {
+                    return Err(BeadsError::UnparsableValue(s.to_string()));
                 } else {
                     continue;
                 }
             }
         };
-        if builder.push_double_with_accuracy(v, accuracy) == false {
-            if builder.push_none() == false {
-                return Err(format!("Could not push value {}", v).to_string())
+        if !builder.push_double_with_accuracy(v, accuracy) && !builder.push_none() {
+            return Err(BeadsError::UnsupportedValue(v.to_string()))
+        }
+    }
+    builder.encode(writer)?;
+    Ok(())
+}
+
+/// Converts strings like `19.99` to decimals of the scale declared with `BeadTypeSet::with_scale`.
+/// Strings with more fraction digits than the scale are not rounded, they are pushed as `None` if possible.
+pub fn string_beads_to_decimal_beads<W>(buffer: &[u8], type_set: &BeadTypeSet, writer: &mut W)  -> Result<(), BeadsError> where W: io::Write {
+    if type_set.scale().is_none() {
+        return Err(BeadsError::MissingScale);
+    }
+    let string_beads = TypedBeads::new(buffer, &BeadTypeSet::new(&[BeadType::Utf8]))?;
+    let mut builder = TypedBeadsBuilder::new(type_set)?;
+    for (index, sb) in string_beads.iter().enumerate() {
+        let s = sb.try_to_str().map_err(|e| BeadsError::InvalidElement { index, error: Box::new(e) })?;
+        let v = match s.parse::<Decimal>() {
+            Ok(v) => v,
+            Err(e) => {
+                if !builder.push_none() {
+                    return Err(e);
+                } else {
+                    continue;
+                }
             }
+        };
+        if !builder.push_decimal(v) && !builder.push_none() {
+            return Err(BeadsError::UnsupportedValue(v.to_string()))
         }
     }
-    builder.encode(writer);
+    builder.encode(writer)?;
     Ok(())
 }
 
-pub fn string_beads_to_indexed_beads<W>(buffer: &[u8], writer: &mut W) -> Result<(), &'static str> where W: io::Write {
+pub fn string_beads_to_indexed_beads<W>(buffer: &[u8], writer: &mut W) -> Result<(), BeadsError> where W: io::Write {
     let string_beads = TypedBeads::new(buffer, &BeadTypeSet::new(&[BeadType::Utf8]))?;
     let mut builder = IndexedBeadsBuilder::new();
     for sb in string_beads.iter() {
         builder.push(sb.to_bytes());
     }
-    builder.encode(writer);
+    builder.encode(writer)?;
     Ok(())
 }
 
 pub fn u128_from_slice(slice: &[u8]) -> u128 {
     let mut tmp: [u8;16] = [0; 16];
-    for i in 0..slice.len() {
-        tmp[i] = slice[i];
-    }
-    u128::from_le_bytes(tmp) as u128
+    tmp[..slice.len()].copy_from_slice(slice);
+    u128::from_le_bytes(tmp)
+}
+
+/// How `beads_to_dedup_beads_with_codes` stores the dictionary index of every value.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DedupCodes {
+    /// Whole bytes per code, as many as the largest code needs.
+    FixedSize,
+    /// As many bits per code as the largest code needs, see `BitPackedBeadsBuilder`.
+    BitPacked,
+}
+
+pub fn beads_to_dedup_beads<W>(buffer: &'_[u8], types: &BeadTypeSet, writer: &mut W) -> Result<(), BeadsError> where W: io::Write {
+    beads_to_dedup_beads_with_codes(buffer, types, DedupCodes::FixedSize, writer)
 }
 
-pub fn beads_to_dedup_beads<W>(buffer: &'_[u8], types: &BeadTypeSet, writer: &mut W) -> Result<(), &'static str> where W: io::Write {
+/// Same as `beads_to_dedup_beads`, with a choice how the codes are stored. `DedupBeads::new` reads both.
+pub fn beads_to_dedup_beads_with_codes<W>(buffer: &'_[u8], types: &BeadTypeSet, codes: DedupCodes, writer: &mut W) -> Result<(), BeadsError> where W: io::Write {
     let beads = TypedBeads::new(buffer, types)?;
     let mut lookup = HashMap::new();
     let mut value_builder = IndexedBeadsBuilder::new();
     let mut index_builder = FixedSizeBeadsIncrementalUintBuilder::new();
+    let mut packed_builder = BitPackedBeadsBuilder::new();
     let mut index = 0;
     for sb in beads.iter() {
         let bytes = sb.to_bytes();
-        if lookup.contains_key(bytes) {
-            index_builder.push(lookup[bytes]);
-            continue
+        let code = match lookup.get(bytes) {
+            Some(code) => *code,
+            None => {
+                lookup.insert(bytes, index);
+                value_builder.push(bytes);
+                index += 1;
+                index - 1
+            }
+        };
+        match codes {
+            DedupCodes::FixedSize => index_builder.push(code),
+            DedupCodes::BitPacked => packed_builder.push(code)
         }
-        lookup.insert(bytes, index);
-        value_builder.push(bytes);
-        index_builder.push(index);
-        index += 1;
+    }
+
+    if codes == DedupCodes::BitPacked {
+        let mut values = vec![];
+        value_builder.encode(&mut values)?;
+        let mut packed = vec![];
+        packed_builder.encode(&mut packed)?;
+        // the first element stays empty, see `DedupBeads::new`
+        let mut root = IndexedBeadsBuilder::new();
+        root.push(&[]);
+        root.push(&values);
+        root.push(&packed);
+        root.encode(writer)?;
+        return Ok(());
     }
 
     let mut builders: Vec<Box<dyn BeadsBuilder + '_>> = Vec::new();
@@ -170,17 +228,35 @@ pub fn beads_to_dedup_beads<W>(buffer: &'_[u8], types: &BeadTypeSet, writer: &mu
     builders.push(index_box);
     builders.push(value_box);
 
-    IndexedBeadsBuilder::encode_from_beads_builders(writer, builders);
+    IndexedBeadsBuilder::encode_from_beads_builders(writer, builders)?;
+    Ok(())
+}
+
+/// Joins several sequences into one sequence with the `target` types. Elements of a type which is
+/// part of the target are copied without decoding their values, other elements are pushed again
+/// like with `TypedBeadsBuilder::append`, which also converts timestamps, durations and decimals to the
+/// unit or scale declared in `target`. An error is reported as `InvalidElement` with the index
+/// of the sequence, around the error of `append`.
+pub fn concat<W>(sequences: &[TypedBeads], target: &BeadTypeSet, writer: &mut W) -> Result<(), BeadsError> where W: io::Write {
+    let mut builder = TypedBeadsBuilder::new(target)?;
+    for (index, beads) in sequences.iter().enumerate() {
+        builder.append(beads).map_err(|e| BeadsError::InvalidElement { index, error: Box::new(e) })?;
+    }
+    builder.encode(writer)?;
     Ok(())
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
-    use crate::converters::{csv_to_indexed_string_beads, string_beads_to_int_beads, string_beads_to_double_beads, string_beads_to_indexed_beads, u128_from_slice, beads_to_dedup_beads};
-    use crate::sequence::{IndexedBeads, TypedBeads, FixedSizeBeads};
-    use crate::bead_type::{BeadTypeSet, BeadType};
+    use crate::converters::{csv_to_indexed_string_beads, string_beads_to_int_beads, string_beads_to_double_beads, string_beads_to_decimal_beads, string_beads_to_indexed_beads, u128_from_slice, beads_to_dedup_beads, beads_to_dedup_beads_with_codes, DedupCodes, concat};
+    use crate::sequence::{IndexedBeads, TypedBeads, FixedSizeBeads, DedupBeads};
+    use crate::bead_type::{BeadTypeSet, BeadType, TimeKind, TimeUnit};
     use std::convert::TryFrom;
+    use std::time::{Duration, UNIX_EPOCH};
     use crate::builder::TypedBeadsBuilder;
+    use crate::error::BeadsError;
+    use crate::decimal::Decimal;
 
     #[test]
     fn empty_string() {
@@ -247,7 +323,7 @@ mod tests {
         builder.push_string("1535340");
 
         let mut buffer: Vec<u8> = vec![];
-        builder.encode(&mut buffer);
+        builder.encode(&mut buffer).unwrap();
 
         let mut out: Vec<u8> = vec![];
         string_beads_to_int_beads(buffer.as_slice(), &BeadTypeSet::new(&[BeadType::I32]), &mut out).unwrap();
@@ -271,7 +347,7 @@ mod tests {
         builder.push_string("255");
 
         let mut buffer: Vec<u8> = vec![];
-        builder.encode(&mut buffer);
+        builder.encode(&mut buffer).unwrap();
 
         let mut out: Vec<u8> = vec![];
         string_beads_to_int_beads(buffer.as_slice(), &BeadTypeSet::new(&[BeadType::U8]), &mut out).unwrap();
@@ -295,7 +371,7 @@ mod tests {
         builder.push_string("-25");
 
         let mut buffer: Vec<u8> = vec![];
-        builder.encode(&mut buffer);
+        builder.encode(&mut buffer).unwrap();
 
         let mut out: Vec<u8> = vec![];
         string_beads_to_int_beads(buffer.as_slice(), &BeadTypeSet::new(&[BeadType::U8, BeadType::I8]), &mut out).unwrap();
@@ -320,7 +396,7 @@ mod tests {
         builder.push_string("-25");
 
         let mut buffer: Vec<u8> = vec![];
-        builder.encode(&mut buffer);
+        builder.encode(&mut buffer).unwrap();
 
         let mut out: Vec<u8> = vec![];
         string_beads_to_int_beads(buffer.as_slice(), &BeadTypeSet::new(&[BeadType::U8, BeadType::None]), &mut out).unwrap();
@@ -339,6 +415,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn convert_string_beads_to_int_reports_unparsable_value() {
+        let mut builder = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Utf8])).ok().unwrap();
+        builder.push_string("1");
+        builder.push_string("one");
+
+        let mut buffer: Vec<u8> = vec![];
+        builder.encode(&mut buffer).unwrap();
+
+        let mut out: Vec<u8> = vec![];
+        let error = string_beads_to_int_beads(buffer.as_slice(), &BeadTypeSet::new(&[BeadType::U8]), &mut out).err().unwrap();
+        assert!(matches!(error, BeadsError::UnparsableValue(ref v) if v == "one"));
+
+        let mut builder = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Utf8])).ok().unwrap();
+        builder.push_string("300");
+        let mut buffer: Vec<u8> = vec![];
+        builder.encode(&mut buffer).unwrap();
+        let error = string_beads_to_int_beads(buffer.as_slice(), &BeadTypeSet::new(&[BeadType::U8]), &mut out).err().unwrap();
+        assert!(matches!(error, BeadsError::UnsupportedValue(ref v) if v == "300"));
+    }
+
+    #[test]
+    fn convert_string_beads_reports_invalid_utf8() {
+        let mut builder = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Utf8])).ok().unwrap();
+        builder.push_string("1");
+        builder.push_string("2");
+        let mut buffer: Vec<u8> = vec![];
+        builder.encode(&mut buffer).unwrap();
+        let last = buffer.len() - 1;
+        buffer[last] = 0xff;
+
+        let mut out: Vec<u8> = vec![];
+        let errors = [
+            string_beads_to_int_beads(&buffer, &BeadTypeSet::new(&[BeadType::U8]), &mut out).err().unwrap(),
+            string_beads_to_double_beads(&buffer, &BeadTypeSet::new(&[BeadType::F64]), 0.0, &mut out).err().unwrap(),
+            string_beads_to_decimal_beads(&buffer, &BeadTypeSet::new(&[BeadType::U8]).with_scale(1).ok().unwrap(), &mut out).err().unwrap(),
+        ];
+        for error in errors {
+            assert_eq!(error.to_string(), "Element 1 is invalid: Value at offset 0 is not a valid utf8 string");
+        }
+    }
+
     #[test]
     fn convert_string_double_beads_to_u8_and_i8() {
         let mut builder = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Utf8])).ok().unwrap();
@@ -348,7 +466,7 @@ mod tests {
         builder.push_string("-25.0");
 
         let mut buffer: Vec<u8> = vec![];
-        builder.encode(&mut buffer);
+        builder.encode(&mut buffer).unwrap();
 
         let mut out: Vec<u8> = vec![];
         string_beads_to_double_beads(buffer.as_slice(), &BeadTypeSet::new(&[BeadType::U8, BeadType::I8]), 0.0, &mut out).unwrap();
@@ -372,10 +490,10 @@ mod tests {
         builder.push_string("-25.0");
 
         let mut buffer: Vec<u8> = vec![];
-        builder.encode(&mut buffer);
+        builder.encode(&mut buffer).unwrap();
 
         let mut out: Vec<u8> = vec![];
-        string_beads_to_double_beads(buffer.as_slice(), &BeadTypeSet::new(&[BeadType::F32]), std::f32::EPSILON as f64, &mut out).unwrap();
+        string_beads_to_double_beads(buffer.as_slice(), &BeadTypeSet::new(&[BeadType::F32]), f32::EPSILON as f64, &mut out).unwrap();
 
         assert_eq!(out, vec![4, 205, 204, 140, 63, 0, 0, 201, 66, 0, 0, 0, 0, 0, 0, 200, 193]);
 
@@ -387,6 +505,46 @@ mod tests {
         assert_eq!(sym_b.get(3).unwrap().to_float(), -25.0);
     }
 
+    #[test]
+    fn convert_string_beads_to_decimal() {
+        let mut builder = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Utf8])).ok().unwrap();
+        let values = ["19.99", "0.1", "-3", "1000000.01", "0.001", "n/a"];
+        for v in values.iter() {
+            builder.push_string(v);
+        }
+        let mut buffer: Vec<u8> = vec![];
+        builder.encode(&mut buffer).unwrap();
+
+        let types = BeadTypeSet::new(&[BeadType::I16, BeadType::I64, BeadType::None]).with_scale(2).ok().unwrap();
+        let mut out: Vec<u8> = vec![];
+        string_beads_to_decimal_beads(&buffer, &types, &mut out).ok().unwrap();
+        let beads = TypedBeads::new(&out, &types).ok().unwrap();
+        assert_eq!(beads.len(), 6);
+        assert_eq!(beads.scale(), Some(2));
+        // only the mantissa 1999 is stored
+        assert_eq!(beads.get(0).ok().unwrap().bead_type, BeadType::I16);
+        assert_eq!(beads.get(0).ok().unwrap().to_int(), 1999);
+        for (i, v) in ["19.99", "0.10", "-3.00", "1000000.01"].iter().enumerate() {
+            assert_eq!(beads.get_decimal(i).ok().unwrap().to_string(), *v);
+        }
+        assert_eq!(beads.get_decimal(1).ok().unwrap(), Decimal::new(10, 2).ok().unwrap());
+        // 0.001 does not fit into the scale
+        assert!(beads.get(4).ok().unwrap().is_none());
+        assert!(beads.get(5).ok().unwrap().is_none());
+
+        // a sequence read without its scale can't give decimals
+        let plain = TypedBeads::new(&out, &BeadTypeSet::new(&[BeadType::I16, BeadType::I64, BeadType::None])).ok().unwrap();
+        assert!(matches!(plain.get_decimal(0).err().unwrap(), BeadsError::MissingScale));
+
+        let mut out: Vec<u8> = vec![];
+        let error = string_beads_to_decimal_beads(&buffer, &BeadTypeSet::new(&[BeadType::I64]).with_scale(2).ok().unwrap(), &mut out).err().unwrap();
+        assert!(matches!(error, BeadsError::UnsupportedValue(v) if v == "0.001"));
+        let error = string_beads_to_decimal_beads(&buffer, &BeadTypeSet::new(&[BeadType::I16]).with_scale(2).ok().unwrap(), &mut out).err().unwrap();
+        assert!(matches!(error, BeadsError::UnsupportedValue(v) if v == "1000000.01"));
+        let error = string_beads_to_decimal_beads(&buffer, &BeadTypeSet::new(&[BeadType::I64]), &mut out).err().unwrap();
+        assert!(matches!(error, BeadsError::MissingScale));
+    }
+
     #[test]
     fn convert_string_beads_to_indexed() {
         let mut builder = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Utf8])).ok().unwrap();
@@ -397,7 +555,7 @@ mod tests {
         builder.push_string("🤪");
 
         let mut buffer: Vec<u8> = vec![];
-        builder.encode(&mut buffer);
+        builder.encode(&mut buffer).unwrap();
 
         let mut out: Vec<u8> = vec![];
         string_beads_to_indexed_beads(&buffer, &mut out).ok().unwrap();
@@ -434,7 +592,7 @@ mod tests {
         builder.push_string("🤪");
 
         let mut buffer: Vec<u8> = vec![];
-        builder.encode(&mut buffer);
+        builder.encode(&mut buffer).unwrap();
 
         let mut out: Vec<u8> = vec![];
         beads_to_dedup_beads(&buffer, &BeadTypeSet::new(&[BeadType::Utf8]), &mut out).ok().unwrap();
@@ -462,4 +620,233 @@ mod tests {
         assert_eq!(&values[u128_from_slice(&index[7]) as usize], "Alex".as_bytes());
         assert_eq!(&values[u128_from_slice(&index[8]) as usize], "🤪".as_bytes());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn convert_to_dedup_beads_with_bit_packed_codes() {
+        let types = BeadTypeSet::new(&[BeadType::U16]);
+        let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+        for i in 0..3000u128 {
+            builder.push_uint((i * 37) % 300);
+        }
+        let mut buffer: Vec<u8> = vec![];
+        builder.encode(&mut buffer).unwrap();
+
+        let mut fixed: Vec<u8> = vec![];
+        beads_to_dedup_beads_with_codes(&buffer, &types, DedupCodes::FixedSize, &mut fixed).ok().unwrap();
+        let mut packed: Vec<u8> = vec![];
+        beads_to_dedup_beads_with_codes(&buffer, &types, DedupCodes::BitPacked, &mut packed).ok().unwrap();
+        // 9 instead of 16 bits per code
+        assert!(packed.len() + 2000 < fixed.len());
+
+        let fixed = DedupBeads::new(&fixed).ok().unwrap();
+        let packed = DedupBeads::new(&packed).ok().unwrap();
+        assert_eq!(packed.len(), 3000);
+        for i in [0, 1, 299, 300, 1500, 2999] {
+            assert_eq!(packed.get(i).ok().unwrap(), fixed.get(i).ok().unwrap());
+            assert_eq!(packed.code(i).ok().unwrap(), fixed.code(i).ok().unwrap());
+        }
+        assert!(packed.iter().eq(fixed.iter()));
+    }
+
+    #[test]
+    fn convert_empty_beads_to_dedup_beads() {
+        let types = BeadTypeSet::new(&[BeadType::Utf8]);
+        let mut buffer: Vec<u8> = vec![];
+        TypedBeadsBuilder::new(&types).ok().unwrap().encode(&mut buffer).unwrap();
+        for codes in [DedupCodes::FixedSize, DedupCodes::BitPacked] {
+            let mut out: Vec<u8> = vec![];
+            beads_to_dedup_beads_with_codes(&buffer, &types, codes, &mut out).ok().unwrap();
+            let dedup = DedupBeads::new(&out).ok().unwrap();
+            assert_eq!(dedup.len(), 0);
+            assert_eq!(dedup.dictionary().len(), 0);
+            assert!(dedup.iter().next().is_none());
+            assert!(matches!(dedup.get(0).err().unwrap(), BeadsError::IndexOutOfBounds { index: 0, len: 0 }));
+        }
+    }
+
+    #[test]
+    fn convert_empty_payloads_to_dedup_beads() {
+        let strings = BeadTypeSet::new(&[BeadType::Utf8]);
+        let bools = BeadTypeSet::new(&[BeadType::TrueFlag, BeadType::FalseFlag]);
+        let nones = BeadTypeSet::new(&[BeadType::None]);
+        let mut columns = vec![];
+        let mut builder = TypedBeadsBuilder::new(&strings).ok().unwrap();
+        builder.push_string("");
+        builder.push_string("");
+        columns.push((strings, builder));
+        let mut builder = TypedBeadsBuilder::new(&bools).ok().unwrap();
+        builder.push_bool(false);
+        builder.push_bool(true);
+        columns.push((bools, builder));
+        let mut builder = TypedBeadsBuilder::new(&nones).ok().unwrap();
+        builder.push_none();
+        builder.push_none();
+        columns.push((nones, builder));
+
+        for (types, builder) in columns {
+            let mut buffer: Vec<u8> = vec![];
+            builder.encode(&mut buffer).unwrap();
+            for codes in [DedupCodes::FixedSize, DedupCodes::BitPacked] {
+                let mut out: Vec<u8> = vec![];
+                beads_to_dedup_beads_with_codes(&buffer, &types, codes, &mut out).ok().unwrap();
+                let dedup = DedupBeads::new(&out).ok().unwrap();
+                assert_eq!(dedup.len(), 2);
+                assert_eq!(dedup.dictionary().len(), 1);
+                assert_eq!(dedup.get(1).ok().unwrap(), &[] as &[u8]);
+                assert_eq!(dedup.iter().count(), 2);
+                assert!(matches!(dedup.get(2).err().unwrap(), BeadsError::IndexOutOfBounds { index: 2, len: 2 }));
+            }
+        }
+
+        let mut buffer: Vec<u8> = vec![];
+        let mut builder = TypedBeadsBuilder::new(&nones).ok().unwrap();
+        builder.push_none();
+        builder.encode(&mut buffer).unwrap();
+        let mut out: Vec<u8> = vec![];
+        beads_to_dedup_beads(&buffer, &nones, &mut out).ok().unwrap();
+        let dedup = DedupBeads::new(&out).ok().unwrap();
+        assert!(dedup.get_bead(0, &nones).ok().unwrap().is_none());
+    }
+
+    fn push_values(builder: &mut TypedBeadsBuilder, from: usize, to: usize) {
+        for i in from..to {
+            match i % 4 {
+                0 => builder.push_none(),
+                1 => builder.push_string(&"x".repeat(i % 7)),
+                _ => builder.push_uint((i % 250) as u128),
+            };
+        }
+    }
+
+    fn encoded(types: &BeadTypeSet, from: usize, to: usize) -> Vec<u8> {
+        let mut builder = TypedBeadsBuilder::new(types).ok().unwrap();
+        push_values(&mut builder, from, to);
+        let mut out = vec![];
+        builder.encode(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn concat_same_types() {
+        let types = BeadTypeSet::new(&[BeadType::U8, BeadType::None, BeadType::Utf8]);
+        let bounds = [0, 8, 21, 21, 26, 30];
+        let buffers: Vec<Vec<u8>> = bounds.windows(2).map(|w| encoded(&types, w[0], w[1])).collect();
+        let sequences: Vec<TypedBeads> = buffers.iter().map(|b| TypedBeads::new(b, &types).ok().unwrap()).collect();
+
+        let mut out: Vec<u8> = vec![];
+        concat(&sequences, &types, &mut out).ok().unwrap();
+        assert_eq!(out, encoded(&types, 0, 30));
+
+        let single = BeadTypeSet::new(&[BeadType::U16]);
+        let mut a = TypedBeadsBuilder::new(&single).ok().unwrap();
+        a.push_uint(1);
+        a.push_uint(300);
+        let mut a_buffer = vec![];
+        a.encode(&mut a_buffer).unwrap();
+        let mut out: Vec<u8> = vec![];
+        let a_beads = TypedBeads::new(&a_buffer, &single).ok().unwrap();
+        concat(&[a_beads, TypedBeads::new(&[0, 0], &single).ok().unwrap(), TypedBeads::new(&a_buffer, &single).ok().unwrap()], &single, &mut out).ok().unwrap();
+        assert_eq!(out, vec![4, 1, 0, 44, 1, 1, 0, 44, 1]);
+    }
+
+    #[test]
+    fn concat_different_time_units() {
+        let millis = BeadTypeSet::new(&[BeadType::I64]).with_time(TimeKind::Timestamp(TimeUnit::Millis));
+        let seconds = BeadTypeSet::new(&[BeadType::I64]).with_time(TimeKind::Timestamp(TimeUnit::Seconds));
+        let encode_times = |types: &BeadTypeSet, times: &[u64]| {
+            let mut builder = TypedBeadsBuilder::new(types).ok().unwrap();
+            for t in times {
+                assert!(builder.push_timestamp(UNIX_EPOCH + Duration::from_millis(*t)));
+            }
+            let mut out = vec![];
+            builder.encode(&mut out).unwrap();
+            out
+        };
+        let a = encode_times(&seconds, &[1_700_000_000_000]);
+        let b = encode_times(&millis, &[1_700_000_001_000, 1_700_000_002_999]);
+        let sequences = vec![TypedBeads::new(&a, &seconds).ok().unwrap(), TypedBeads::new(&b, &millis).ok().unwrap()];
+
+        let mut out: Vec<u8> = vec![];
+        concat(&sequences, &seconds, &mut out).ok().unwrap();
+        let beads = TypedBeads::new(&out, &seconds).ok().unwrap();
+        let values: Vec<i128> = beads.iter().map(|b| b.to_int()).collect();
+        // millis are rounded down to whole seconds, like with `push_timestamp`
+        assert_eq!(values, vec![1_700_000_000, 1_700_000_001, 1_700_000_002]);
+
+        let mut out: Vec<u8> = vec![];
+        concat(&sequences, &millis, &mut out).ok().unwrap();
+        let beads = TypedBeads::new(&out, &millis).ok().unwrap();
+        assert_eq!(beads.get(0).ok().unwrap().to_int(), 1_700_000_000_000);
+        assert_eq!(beads.get(2).ok().unwrap().to_int(), 1_700_000_002_999);
+
+        // durations, plain ints and timestamps don't mix
+        let durations = BeadTypeSet::new(&[BeadType::I64]).with_time(TimeKind::Duration(TimeUnit::Millis));
+        let error = concat(&sequences, &durations, &mut out).err().unwrap();
+        assert_eq!(error.to_string(), "Element 0 is invalid: Can't append timestamp (Seconds) values to duration (Millis) values");
+        let error = concat(&sequences, &BeadTypeSet::new(&[BeadType::I64]), &mut out).err().unwrap();
+        assert!(matches!(error, BeadsError::InvalidElement { index: 0, error } if matches!(*error, BeadsError::KindMismatch { .. })));
+    }
+
+    #[test]
+    fn concat_different_scales() {
+        let encode_decimals = |types: &BeadTypeSet, values: &[&str]| {
+            let mut builder = TypedBeadsBuilder::new(types).ok().unwrap();
+            for v in values {
+                assert!(builder.push_decimal(v.parse().ok().unwrap()));
+            }
+            let mut out = vec![];
+            builder.encode(&mut out).unwrap();
+            out
+        };
+        let cents = BeadTypeSet::new(&[BeadType::I32, BeadType::I64]).with_scale(2).ok().unwrap();
+        let fine = BeadTypeSet::new(&[BeadType::I32, BeadType::I64]).with_scale(4).ok().unwrap();
+        let a = encode_decimals(&cents, &["19.99", "-0.5"]);
+        let b = encode_decimals(&fine, &["0.1234", "7.5"]);
+        let sequences = vec![TypedBeads::new(&a, &cents).ok().unwrap(), TypedBeads::new(&b, &fine).ok().unwrap()];
+
+        let mut out: Vec<u8> = vec![];
+        concat(&sequences, &fine, &mut out).ok().unwrap();
+        let beads = TypedBeads::new(&out, &fine).ok().unwrap();
+        let values: Vec<String> = (0..4).map(|i| beads.get_decimal(i).ok().unwrap().to_string()).collect();
+        assert_eq!(values, ["19.9900", "-0.5000", "0.1234", "7.5000"]);
+
+        // 0.1234 has more fraction digits than the target scale
+        let error = concat(&sequences, &cents, &mut out).err().unwrap();
+        assert_eq!(error.to_string(), "Element 1 is invalid: Element 0 is invalid: Could not push value 1234");
+        let mut out: Vec<u8> = vec![];
+        concat(&sequences[..1], &cents, &mut out).ok().unwrap();
+        assert_eq!(out, a);
+
+        let error = concat(&sequences, &BeadTypeSet::new(&[BeadType::I32, BeadType::I64]), &mut out).err().unwrap();
+        assert_eq!(error.to_string(), "Element 0 is invalid: Can't append decimal (scale 2) values to plain values");
+    }
+
+    #[test]
+    fn concat_different_types() {
+        let small = BeadTypeSet::new(&[BeadType::U8, BeadType::None, BeadType::Utf8]);
+        let wide = BeadTypeSet::new(&[BeadType::U16, BeadType::None, BeadType::Utf8]);
+        let target = BeadTypeSet::new(&[BeadType::U16, BeadType::None, BeadType::Bytes, BeadType::F64]);
+        let a = encoded(&small, 0, 11);
+        let b = encoded(&wide, 11, 30);
+        let sequences = vec![TypedBeads::new(&a, &small).ok().unwrap(), TypedBeads::new(&b, &wide).ok().unwrap()];
+
+        let mut out: Vec<u8> = vec![];
+        concat(&sequences, &target, &mut out).ok().unwrap();
+        let beads = TypedBeads::new(&out, &target).ok().unwrap();
+        assert!(beads.validate().is_ok());
+        assert_eq!(beads.len(), 30);
+        for (i, bead) in beads.iter().enumerate() {
+            match i % 4 {
+                0 => assert!(bead.is_none()),
+                1 => assert_eq!(bead.to_bytes(), "x".repeat(i % 7).as_bytes()),
+                _ => {
+                    assert_eq!(bead.bead_type, BeadType::U16);
+                    assert_eq!(bead.to_uint(), (i % 250) as u128);
+                }
+            }
+        }
+
+        let error = concat(&sequences, &BeadTypeSet::new(&[BeadType::U8, BeadType::None]), &mut out).err().unwrap();
+        assert_eq!(error.to_string(), "Element 0 is invalid: Element 1 is invalid: Could not push value 'x'");
+    }
+}
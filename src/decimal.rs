@@ -0,0 +1,141 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::error::BeadsError;
+
+/// Largest number of fraction digits a `Decimal` can have, the scale of a type set takes 4 bits.
+pub const MAX_SCALE: u8 = 15;
+
+/// An exact decimal number `mantissa * 10^-scale`, for example `19.99` is `Decimal::new(1999, 2)`.
+/// Values with a different scale are different, so `19.9` and `19.90` are not equal.
+/// A sequence stores only the mantissas, its scale is declared with `BeadTypeSet::with_scale`.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    /// Fails if `scale` is larger than `MAX_SCALE`.
+    pub fn new(mantissa: i128, scale: u8) -> Result<Decimal, BeadsError> {
+        if scale > MAX_SCALE {
+            return Err(BeadsError::OutOfRange { value: format!("{}e-{}", mantissa, scale), target: "Decimal" });
+        }
+        Ok(Decimal { mantissa, scale })
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.mantissa as f64 / 10f64.powi(self.scale as i32)
+    }
+
+    /// The same number with `scale` fraction digits, `None` if digits would be lost or the mantissa overflows.
+    pub fn rescale(&self, scale: u8) -> Option<Decimal> {
+        if scale > MAX_SCALE {
+            return None;
+        }
+        let mantissa = if scale >= self.scale {
+            self.mantissa.checked_mul(10i128.pow((scale - self.scale) as u32))?
+        } else {
+            let divisor = 10i128.pow((self.scale - scale) as u32);
+            if self.mantissa % divisor != 0 {
+                return None;
+            }
+            self.mantissa / divisor
+        };
+        Some(Decimal { mantissa, scale })
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = BeadsError;
+
+    /// Parses numbers like `19.99`, `-0.5` or `42`, without exponent.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unparsable = || BeadsError::UnparsableValue(s.to_string());
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s)
+        };
+        let (integer, fraction) = match digits.find('.') {
+            Some(dot) => (&digits[..dot], &digits[dot + 1..]),
+            None => (digits, "")
+        };
+        if integer.len() + fraction.len() == 0 || integer.bytes().chain(fraction.bytes()).any(|b| !b.is_ascii_digit()) {
+            return Err(unparsable());
+        }
+        if fraction.len() > MAX_SCALE as usize {
+            return Err(BeadsError::OutOfRange { value: s.to_string(), target: "Decimal" });
+        }
+        let mut mantissa = 0i128;
+        for b in integer.bytes().chain(fraction.bytes()) {
+            let digit = (b - b'0') as i128;
+            mantissa = mantissa.checked_mul(10)
+                .and_then(|m| if negative { m.checked_sub(digit) } else { m.checked_add(digit) })
+                .ok_or_else(|| BeadsError::OutOfRange { value: s.to_string(), target: "Decimal" })?;
+        }
+        Ok(Decimal { mantissa, scale: fraction.len() as u8 })
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        if self.mantissa < 0 {
+            write!(f, "-")?;
+        }
+        if scale == 0 {
+            return write!(f, "{}", digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}.{}", integer, fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decimal::Decimal;
+    use crate::error::BeadsError;
+
+    #[test]
+    fn parse_and_display() {
+        for s in ["19.99", "-19.99", "0.05", "-0.5", "42", "0", "1.000", "-170141183460469231731687303715884105728"] {
+            let d: Decimal = s.parse().ok().unwrap();
+            assert_eq!(d.to_string(), s);
+        }
+        let d: Decimal = "19.99".parse().ok().unwrap();
+        assert_eq!((d.mantissa(), d.scale()), (1999, 2));
+        assert_eq!(d.to_f64(), 19.99);
+        assert_ne!(d, "19.990".parse().ok().unwrap());
+        assert_eq!("+.5".parse::<Decimal>().ok().unwrap().to_string(), "0.5");
+        assert_eq!("7.".parse::<Decimal>().ok().unwrap().to_string(), "7");
+
+        for s in ["", "-", ".", "1e5", "1.2.3", "abc", "- 1", "NaN"] {
+            assert!(matches!(s.parse::<Decimal>().err().unwrap(), BeadsError::UnparsableValue(_)), "{}", s);
+        }
+        for s in ["0.1234567890123456", "170141183460469231731687303715884105728"] {
+            assert!(matches!(s.parse::<Decimal>().err().unwrap(), BeadsError::OutOfRange { .. }), "{}", s);
+        }
+        assert!(Decimal::new(1, 16).is_err());
+        assert!(Decimal::new(i128::MIN, 15).is_ok());
+    }
+
+    #[test]
+    fn rescale() {
+        let d = Decimal::new(-1990, 3).ok().unwrap();
+        assert_eq!(d.rescale(5), Some(Decimal::new(-199000, 5).ok().unwrap()));
+        assert_eq!(d.rescale(2), Some(Decimal::new(-199, 2).ok().unwrap()));
+        assert_eq!(d.rescale(1), None);
+        assert_eq!(d.rescale(16), None);
+        assert_eq!(Decimal::new(i128::MAX, 0).ok().unwrap().rescale(1), None);
+    }
+}
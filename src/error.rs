@@ -0,0 +1,143 @@
+use std::fmt;
+use std::io;
+use crate::bead_type::BeadType;
+
+#[derive(Debug)]
+pub enum BeadsError {
+    /// Reading `needed` bytes at `offset` would go past the end of the buffer.
+    TruncatedBuffer { offset: usize, needed: usize },
+    /// The VLQ starting at `offset` is not terminated or does not fit into 128 bits.
+    InvalidVlq { offset: usize },
+    /// The tag at `offset` points to type number `tag`, but the type set has only `types` entries.
+    InvalidTag { offset: usize, tag: usize, types: usize },
+    /// A Beads sequence can carry only 1..=16 types.
+    TypeSetSize(usize),
+    /// The string payload has an invalid UTF-8 sequence at `offset`.
+    InvalidUtf8 { offset: usize },
+    /// A bead of type `found` was read as a `expected` value.
+    TypeMismatch { found: BeadType, expected: &'static str },
+    /// The decoded `value` does not fit into the `target` type.
+    OutOfRange { value: String, target: &'static str },
+    /// Element number `index` was requested from a sequence of `len` elements.
+    IndexOutOfBounds { index: usize, len: usize },
+    /// The index entry at `offset` points before the end of the previous entry.
+    InvalidIndexEntry { offset: usize },
+    /// The sequence ends at `offset`, but the buffer has `count` more bytes.
+    TrailingBytes { offset: usize, count: usize },
+    /// Element number `index` of a sequence could not be decoded or pushed.
+    InvalidElement { index: usize, error: Box<BeadsError> },
+    /// The value of `field` in row number `row` of a columnar record could not be pushed.
+    InvalidField { field: &'static str, row: usize, error: Box<BeadsError> },
+    /// A `SymmetricTypedBeads` needs all types of the sequence to have the same fixed size.
+    NotSymmetrical,
+    /// A textual value could not be parsed into the target type.
+    UnparsableValue(String),
+    /// A value could not be pushed with the configured type set.
+    UnsupportedValue(String),
+    /// Writing or reading the encoded bytes failed.
+    Io(io::Error),
+    /// A sequence of `found` values was appended to a builder of `expected` values,
+    /// for example durations to timestamps.
+    KindMismatch { found: String, expected: String },
+    /// Decimal values were read from a sequence which was not declared with `BeadTypeSet::with_scale`.
+    MissingScale,
+    /// Column number `column` holds `len` elements, while the first column holds `rows`.
+    ColumnLengthMismatch { column: usize, len: usize, rows: usize },
+    /// Values of the types `first` and `second` can have payloads of the same size,
+    /// so a payload without its tag can't be read as one of them.
+    AmbiguousTypes { first: BeadType, second: BeadType },
+    /// Free form error, raised for example by serde implementations.
+    Custom(String),
+}
+
+impl BeadsError {
+    /// Moves the reported byte offset by `by`, used when a nested buffer is decoded
+    /// and the error should point into the enclosing one.
+    pub(crate) fn shift(self, by: usize) -> BeadsError {
+        match self {
+            BeadsError::TruncatedBuffer { offset, needed } => BeadsError::TruncatedBuffer { offset: offset + by, needed },
+            BeadsError::InvalidVlq { offset } => BeadsError::InvalidVlq { offset: offset + by },
+            BeadsError::InvalidTag { offset, tag, types } => BeadsError::InvalidTag { offset: offset + by, tag, types },
+            BeadsError::InvalidUtf8 { offset } => BeadsError::InvalidUtf8 { offset: offset + by },
+            BeadsError::TrailingBytes { offset, count } => BeadsError::TrailingBytes { offset: offset + by, count },
+            BeadsError::InvalidIndexEntry { offset } => BeadsError::InvalidIndexEntry { offset: offset + by },
+            BeadsError::InvalidElement { index, error } => BeadsError::InvalidElement { index, error: Box::new(error.shift(by)) },
+            BeadsError::InvalidField { field, row, error } => BeadsError::InvalidField { field, row, error: Box::new(error.shift(by)) },
+            other => other
+        }
+    }
+
+    /// Byte offset the error refers to, if there is one.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            BeadsError::TruncatedBuffer { offset, .. }
+            | BeadsError::InvalidVlq { offset }
+            | BeadsError::InvalidTag { offset, .. }
+            | BeadsError::InvalidUtf8 { offset }
+            | BeadsError::TrailingBytes { offset, .. }
+            | BeadsError::InvalidIndexEntry { offset } => Some(*offset),
+            BeadsError::InvalidElement { error, .. } | BeadsError::InvalidField { error, .. } => error.offset(),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for BeadsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BeadsError::TruncatedBuffer { offset, needed } =>
+                write!(f, "Buffer is too short to read {} byte(s) at offset {}", needed, offset),
+            BeadsError::InvalidVlq { offset } =>
+                write!(f, "Buffer does not contain a valid vlq at offset {}", offset),
+            BeadsError::InvalidTag { offset, tag, types } =>
+                write!(f, "Tag {} at offset {} is out of range for {} type(s)", tag, offset, types),
+            BeadsError::TypeSetSize(size) =>
+                write!(f, "Beads sequence can carry only 1..=16 types, got {}", size),
+            BeadsError::InvalidUtf8 { offset } =>
+                write!(f, "Value at offset {} is not a valid utf8 string", offset),
+            BeadsError::TypeMismatch { found, expected } =>
+                write!(f, "Not a {} value, found {:?}", expected, found),
+            BeadsError::OutOfRange { value, target } =>
+                write!(f, "Value {} does not fit into {}", value, target),
+            BeadsError::IndexOutOfBounds { index, len } =>
+                write!(f, "Bad index: {} where count is: {}", index, len),
+            BeadsError::InvalidIndexEntry { offset } =>
+                write!(f, "Index entry at offset {} points before the previous entry", offset),
+            BeadsError::TrailingBytes { offset, count } =>
+                write!(f, "Sequence ends at offset {}, but buffer has {} more byte(s)", offset, count),
+            BeadsError::InvalidElement { index, error } =>
+                write!(f, "Element {} is invalid: {}", index, error),
+            BeadsError::InvalidField { field, row, error } =>
+                write!(f, "Field {} in row {} is invalid: {}", field, row, error),
+            BeadsError::NotSymmetrical => write!(f, "Beads sequence is not symmetrical"),
+            BeadsError::UnparsableValue(value) => write!(f, "Could not parse value '{}'", value),
+            BeadsError::UnsupportedValue(value) => write!(f, "Could not push value {}", value),
+            BeadsError::Io(e) => write!(f, "I/O error: {}", e),
+            BeadsError::KindMismatch { found, expected } =>
+                write!(f, "Can't append {} values to {} values", found, expected),
+            BeadsError::MissingScale =>
+                write!(f, "Sequence has no decimal scale"),
+            BeadsError::ColumnLengthMismatch { column, len, rows } =>
+                write!(f, "Column {} has {} element(s), expected {}", column, len, rows),
+            BeadsError::AmbiguousTypes { first, second } =>
+                write!(f, "Payloads of {:?} and {:?} values can't be told apart", first, second),
+            BeadsError::Custom(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for BeadsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BeadsError::Io(e) => Some(e),
+            BeadsError::InvalidElement { error, .. } | BeadsError::InvalidField { error, .. } => Some(error.as_ref()),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for BeadsError {
+    fn from(e: io::Error) -> Self {
+        BeadsError::Io(e)
+    }
+}
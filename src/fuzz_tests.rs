@@ -0,0 +1,316 @@
+use crate::bead_type::{BeadType, BeadTypeSet};
+use crate::builder::{TypedBeadsBuilder, IndexedBeadsBuilder, FixedSizeBeadsBuilder, DeltaBeadsBuilder, RleBeadsBuilder, BitPackedBeadsBuilder};
+use crate::sequence::{TypedBeads, IndexedBeads, FixedSizeBeads, DedupBeads, DeltaBeads, RleBeads, BitPackedBeads};
+use crate::converters::{beads_to_dedup_beads, beads_to_dedup_beads_with_codes, DedupCodes};
+use crate::reader::BeadsReader;
+
+// Every decoding entry point gets random and mutated buffers and has to answer with
+// an error instead of a panic. The generator is seeded, so a failure can be replayed.
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn bytes(&mut self, max_len: usize) -> Vec<u8> {
+        let len = self.below(max_len + 1);
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+}
+
+fn type_sets() -> Vec<BeadTypeSet> {
+    vec![
+        BeadTypeSet::new(&[BeadType::U8]),
+        BeadTypeSet::new(&[BeadType::None]),
+        BeadTypeSet::new(&[BeadType::Utf8]),
+        BeadTypeSet::new(&[BeadType::U128, BeadType::I128]),
+        BeadTypeSet::new(&[BeadType::TrueFlag, BeadType::FalseFlag, BeadType::None]),
+        BeadTypeSet::new(&[BeadType::U8, BeadType::I8, BeadType::None, BeadType::Utf8]),
+        BeadTypeSet::new(&[BeadType::U32, BeadType::I32, BeadType::F32, BeadType::None]),
+        BeadTypeSet::new(&[BeadType::Vlq, BeadType::VlqZ, BeadType::Bytes, BeadType::F16, BeadType::U64, BeadType::TrueFlag]),
+    ]
+}
+
+fn exercise_typed(mut beads: TypedBeads) {
+    if beads.validate().is_ok() {
+        let stats = beads.stats().ok().unwrap();
+        assert_eq!(stats.count, beads.len());
+        // a slice of a valid sequence is valid as well
+        let start = std::cmp::min(beads.len() / 3, 500);
+        let end = std::cmp::min(beads.len(), start + 1000);
+        let mut out = vec![];
+        beads.slice(start..end, &mut out).ok().unwrap();
+        let sliced = TypedBeads::new(&out, &BeadTypeSet::new(beads.types())).ok().unwrap();
+        assert_eq!(sliced.len(), end - start);
+        assert!(sliced.validate().is_ok());
+    } else {
+        let _ = beads.split_at(beads.len() / 2);
+        let _ = beads.stats();
+    }
+    let _ = beads.get(0);
+    let _ = beads.get(usize::MAX);
+    if beads.build_seek_index(3).is_ok() {
+        let _ = beads.get(beads.len().saturating_sub(1));
+    }
+    for bead in beads.iter().rev().take(16) {
+        let _ = bead.try_to_uint();
+    }
+    let _ = beads.iter().nth(200);
+    for bead in beads.iter().take(256) {
+        let _ = bead.try_to_bool();
+        let _ = bead.try_to_str();
+        let _ = bead.try_to_uint();
+        let _ = bead.try_to_int();
+        let _ = bead.try_to_float();
+        let _ = bead.to_bytes();
+        if let Ok(nested) = bead.as_typed_beads_types_included() {
+            let _ = nested.validate();
+        }
+        if let Ok(nested) = bead.as_indexed_beads() {
+            let _ = nested.last();
+        }
+    }
+    if let Ok(symb) = beads.symmetric() {
+        for i in 0..std::cmp::min(symb.len(), 64) {
+            if let Ok(bead) = symb.get(i) {
+                let _ = bead.try_to_int();
+                let _ = bead.try_to_float();
+            }
+        }
+        let _ = symb.get(symb.len().saturating_sub(1));
+        let _ = symb.get(usize::MAX);
+    }
+}
+
+// a sequence which could be appended has to come out as a valid sequence again
+fn exercise_append(buffer: &[u8], beads: &TypedBeads, types: &BeadTypeSet) {
+    let mut builder = TypedBeadsBuilder::new(types).ok().unwrap();
+    builder.push_none();
+    if builder.append(beads).is_ok() && builder.append(beads).is_ok() {
+        let mut out = vec![];
+        builder.encode(&mut out).unwrap();
+        let appended = TypedBeads::new(&out, types).ok().unwrap();
+        assert!(appended.validate().is_ok());
+        assert_eq!(appended.len(), beads.len() * 2 + if types.contains(&BeadType::None) { 1 } else { 0 });
+    }
+    if let Ok(mut builder) = TypedBeadsBuilder::from_encoded(buffer, types) {
+        builder.push_uint(1);
+    }
+}
+
+fn exercise(buffer: &[u8]) {
+    for types in type_sets() {
+        if let Ok(beads) = TypedBeads::new(buffer, &types) {
+            exercise_append(buffer, &beads, &types);
+            exercise_typed(beads);
+        }
+    }
+    if let Ok(beads) = TypedBeads::new_types_included(buffer) {
+        exercise_typed(beads);
+    }
+    if let Ok(mut builder) = TypedBeadsBuilder::from_encoded_types_included(buffer) {
+        builder.push_none();
+    }
+    for types in type_sets() {
+        if let Ok(reader) = BeadsReader::new(buffer, &types) {
+            for bead in reader.take(256) {
+                let _ = bead.map(|b| b.as_bead().try_to_int());
+            }
+        }
+    }
+    if let Ok(reader) = BeadsReader::new_types_included(buffer) {
+        for bead in reader.take(256) {
+            let _ = bead.map(|b| b.as_bead().try_to_float());
+        }
+    }
+    if let Ok(indexed) = IndexedBeads::new(buffer) {
+        for i in 0..std::cmp::min(indexed.len(), 64) {
+            if let Ok(child) = indexed.get(i) {
+                let _ = FixedSizeBeads::new(child).map(|f| f.len());
+            }
+        }
+        let _ = indexed.get(indexed.len().saturating_sub(1));
+        let _ = indexed.get(usize::MAX);
+        let _ = indexed.iter().rev().take(64).count();
+        let _ = indexed.last();
+        if let Ok(tail) = indexed.slice(indexed.len() / 2..) {
+            let _ = tail.iter().nth(3);
+            let _ = tail.get_indexed(0).map(|i| i.first());
+            let _ = tail.get_typed(0, &BeadTypeSet::new(&[BeadType::U8, BeadType::Utf8]));
+        }
+    }
+    if let Ok(fixed) = FixedSizeBeads::new(buffer) {
+        for i in 0..std::cmp::min(fixed.len(), 64) {
+            let _ = fixed.get(i);
+        }
+        let _ = fixed.get(fixed.len());
+        let _ = fixed.get(usize::MAX);
+        let _ = fixed.get_u32(0);
+        let _ = fixed.get_u128(fixed.len().saturating_sub(1));
+        let _ = fixed.iter_uint().map(|i| i.rev().take(64).count());
+        let _ = fixed.as_slice::<u16>().map(|s| s.len());
+    }
+    if let Ok(dedup) = DedupBeads::new(buffer) {
+        for i in 0..std::cmp::min(dedup.len(), 64) {
+            let _ = dedup.get(i);
+            let _ = dedup.get_bead(i, &BeadTypeSet::new(&[BeadType::Vlq, BeadType::U16, BeadType::Utf8]));
+        }
+        let _ = dedup.get(usize::MAX);
+        let _ = dedup.iter().rev().take(64).count();
+        let _ = dedup.dictionary_beads(&BeadTypeSet::new(&[BeadType::VlqZ, BeadType::Bytes]));
+    }
+    if let Ok(delta) = DeltaBeads::new_types_included(buffer) {
+        let _ = delta.iter().take(256).count();
+        let _ = delta.to_vec();
+    }
+    if let Ok(delta) = DeltaBeads::new(buffer, &BeadTypeSet::new(&[BeadType::I8, BeadType::I128, BeadType::None])) {
+        let _ = delta.to_vec();
+    }
+    if let Ok(rle) = RleBeads::new_types_included(buffer) {
+        let _ = rle.get(rle.len().saturating_sub(1));
+        let _ = rle.get(rle.len() / 2);
+        // a valid sequence has a value for every element, even for long runs
+        assert!(rle.is_empty() || rle.iter().nth(rle.len() - 1).is_some());
+        let _ = rle.iter().take(256).count();
+    }
+    if let Ok(packed) = BitPackedBeads::new(buffer) {
+        let _ = packed.get(packed.len().saturating_sub(1));
+        let _ = packed.get(usize::MAX);
+        let _ = packed.iter().take(256).count();
+    }
+}
+
+fn valid_samples() -> Vec<Vec<u8>> {
+    let mut samples = vec![];
+
+    for types in type_sets() {
+        let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+        for i in 0..40i128 {
+            match i % 6 {
+                0 => builder.push_none(),
+                1 => builder.push_int(-i * 1000),
+                2 => builder.push_string("Hello 🤪"),
+                3 => builder.push_bool(i % 4 == 1),
+                4 => builder.push_bytes(&[1, 2, 3]),
+                _ => builder.push_double(0.5),
+            };
+        }
+        let mut out = vec![];
+        builder.encode(&mut out).unwrap();
+        samples.push(out.clone());
+
+        let mut with_types = vec![];
+        builder.encode_with_types(&mut with_types).unwrap();
+        samples.push(with_types);
+
+        let mut dedup = vec![];
+        if beads_to_dedup_beads(&out, &types, &mut dedup).is_ok() {
+            samples.push(dedup);
+        }
+        let mut dedup = vec![];
+        if beads_to_dedup_beads_with_codes(&out, &types, DedupCodes::BitPacked, &mut dedup).is_ok() {
+            samples.push(dedup);
+        }
+    }
+
+    let mut indexed = IndexedBeadsBuilder::new();
+    indexed.push(&[1, 2, 3]);
+    indexed.push(&[4, 5]);
+    indexed.push(&[]);
+    let mut out = vec![];
+    indexed.encode(&mut out).unwrap();
+    samples.push(out);
+
+    let mut delta = DeltaBeadsBuilder::with_delta_of_delta(&BeadTypeSet::new(&[BeadType::I8, BeadType::I128])).ok().unwrap();
+    for i in 0..40i128 {
+        delta.push_int(i * i * 1000);
+    }
+    let mut out = vec![];
+    delta.encode_with_types(&mut out).unwrap();
+    samples.push(out);
+
+    let mut rle = RleBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::None, BeadType::U8, BeadType::Utf8])).ok().unwrap();
+    for i in 0..200u128 {
+        if i % 50 < 30 { rle.push_uint(i / 50) } else { rle.push_string("run") };
+    }
+    let mut out = vec![];
+    rle.encode_with_types(&mut out).unwrap();
+    samples.push(out);
+
+    let mut packed = BitPackedBeadsBuilder::new();
+    for i in 0..40u128 {
+        packed.push(1000 + i * i * 17);
+    }
+    let mut out = vec![];
+    packed.encode(&mut out).unwrap();
+    samples.push(out);
+
+    let mut fixed = FixedSizeBeadsBuilder::new(3);
+    fixed.push(&[1, 2, 3]);
+    fixed.push(&[4, 5, 6]);
+    let mut out = vec![];
+    fixed.encode(&mut out).unwrap();
+    samples.push(out);
+
+    samples
+}
+
+#[test]
+fn random_buffers_do_not_panic() {
+    let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+    for _ in 0..3_000 {
+        let buffer = rng.bytes(48);
+        exercise(&buffer);
+    }
+}
+
+#[test]
+fn mutated_buffers_do_not_panic() {
+    let mut rng = XorShift(0xD1B5_4A32_D192_ED03);
+    for sample in valid_samples() {
+        exercise(&sample);
+        for _ in 0..200 {
+            let mut buffer = sample.clone();
+            match rng.below(4) {
+                0 => {
+                    let i = rng.below(buffer.len());
+                    buffer[i] ^= 1 << rng.below(8);
+                }
+                1 => buffer.truncate(rng.below(sample.len())),
+                2 => {
+                    let i = rng.below(buffer.len());
+                    buffer[i] = rng.next() as u8;
+                }
+                _ => {
+                    let i = rng.below(buffer.len());
+                    buffer.insert(i, 0xff);
+                }
+            }
+            exercise(&buffer);
+        }
+    }
+}
+
+#[test]
+fn hostile_headers_do_not_panic() {
+    // long vlq continuation runs
+    exercise(&[0xff; 40]);
+    exercise(&[0x80; 300]);
+    // huge count with 8 byte index entries
+    exercise(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, 1, 2, 3]);
+    // fixed size of zero
+    exercise(&[0, 1, 2, 3]);
+    // type set with unknown bits only
+    exercise(&[0, 0, 0, 0x80, 3, 1, 2, 3]);
+    // index entries going backwards
+    exercise(&[16, 4, 2, 1, 2, 3, 4]);
+}
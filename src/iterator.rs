@@ -1,61 +1,110 @@
 use std::convert::AsRef;
 use crate::bead_type::BeadType;
 use std::cmp::max;
+use std::rc::Rc;
+use std::convert::TryFrom;
 use crate::vlq::read_vlq;
 use crate::reference::BeadReference;
+use crate::error::BeadsError;
+use crate::sequence::{IndexedBeads, DedupBeads, BitPackedBeads};
 
+// distance between the positions recorded for reverse iteration, when there is no seek index
+const BACK_CHECKPOINT_STEP: usize = 64;
+
+/// Decoder positions of every `step`-th element, starting with element `first`.
+#[derive(Clone)]
+pub(crate) struct Checkpoints {
+    pub(crate) first: usize,
+    pub(crate) step: usize,
+    pub(crate) positions: Rc<Vec<(usize, usize, usize)>>,
+}
+
+impl Checkpoints {
+    /// The closest recorded position at or before element `index`.
+    pub(crate) fn before(&self, index: usize) -> Option<(usize, usize, usize)> {
+        if index < self.first {
+            return None;
+        }
+        let last = self.positions.len().checked_sub(1)?;
+        Some(self.positions[std::cmp::min((index - self.first) / self.step, last)])
+    }
+}
+
+/// Iterator over the elements of a `TypedBeads` sequence.
+///
+/// The iterator stops at the first element which can't be decoded. `len` is the number of
+/// elements the header claims, so it is only exact for sequences which pass `TypedBeads::validate`,
+/// a damaged sequence can end before `len` elements were returned.
 pub struct BeadsIterator<'a> {
     buffer: &'a[u8],
-    types: &'a Vec<BeadType>,
-    count: usize,
+    // copied, so the elements only borrow from the buffer
+    types: [BeadType; 16],
+    type_count: usize,
     index: usize,
+    // elements from `back` on were already returned by `next_back`
+    back: usize,
     tag_cursor: usize,
     data_cursor: usize,
     tags_per_byte: usize,
     tag_mask: u8,
+    checkpoints: Option<Checkpoints>,
+    // decoded elements before `back`, handed out by `next_back` from the end
+    back_block: Vec<BeadReference<'a>>,
 }
 
 impl <'a> Iterator for BeadsIterator<'a> {
     type Item = BeadReference<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index >= self.count {
-            return None;
-        }
+        self.try_next()?.ok()
+    }
 
-        let bead_type = if self.types.len() == 1 {self.types[0]} else {self.get_type()};
-        let tag_addition = if bead_type.has_no_data() { 0 } else { 1 };
-        let mut start = if self.types.len() == 1 {self.data_cursor} else {max(self.data_cursor, self.tag_cursor + tag_addition)};
-        let (data_length, data_value) = Self::get_data_length_and_value(self.buffer[start..].as_ref(), bead_type).ok()?;
-        self.data_cursor = start + data_length;
-        if bead_type == BeadType::Utf8 || bead_type == BeadType::Bytes {
-            self.data_cursor += data_value as usize;
-            start += data_length;
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.index;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.try_nth(n)?.ok()
+    }
+
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+
+// exact for validated sequences, see the notes on `BeadsIterator`
+impl ExactSizeIterator for BeadsIterator<'_> {}
+
+impl <'a> DoubleEndedIterator for BeadsIterator<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back <= self.index {
+            return None;
         }
-        self.index += 1;
-        if self.buffer.len() < self.data_cursor {
+        if self.back_block.is_empty() && self.fill_back_block().is_err() {
+            self.exhaust();
             return None;
         }
-        Some(BeadReference {
-            value: data_value,
-            buffer: self.buffer[start..self.data_cursor].as_ref(),
-            bead_type
-        })
+        self.back -= 1;
+        self.back_block.pop()
     }
 }
 
-impl <'a> BeadsIterator <'_> {
-    pub(crate) fn new (buffer: &'a [u8], count: usize, types: &'a Vec<BeadType>) -> BeadsIterator<'a> {
+impl <'a> BeadsIterator <'a> {
+    pub(crate) fn new (buffer: &'a [u8], count: usize, types: &[BeadType]) -> BeadsIterator<'a> {
         let mask = match types.len() {
             1..=2 => 1,
             3..=4 => 3,
             _ => 15
         };
+        let mut _types = [BeadType::None; 16];
+        _types[..types.len()].copy_from_slice(types);
         BeadsIterator {
             buffer,
-            types,
-            count,
+            types: _types,
+            type_count: types.len(),
             index: 0,
+            back: count,
             tag_cursor: 0,
             data_cursor: 0,
             tag_mask: mask,
@@ -63,31 +112,460 @@ impl <'a> BeadsIterator <'_> {
                 1..=2 => 8,
                 3..=4 => 4,
                 _ => 2
+            },
+            checkpoints: None,
+            back_block: vec![],
+        }
+    }
+
+    pub(crate) fn with_checkpoints(mut self, checkpoints: Option<Checkpoints>) -> BeadsIterator<'a> {
+        self.checkpoints = checkpoints;
+        self
+    }
+
+    /// Decodes the next element, reporting why it could not be decoded instead of just stopping.
+    /// After an error the iterator is exhausted. Offsets are relative to the data section of the sequence.
+    pub(crate) fn try_next(&mut self) -> Option<Result<BeadReference<'a>, BeadsError>> {
+        if self.index >= self.back {
+            return None;
+        }
+        let result = self.read_next();
+        if result.is_err() {
+            self.exhaust();
+        }
+        Some(result)
+    }
+
+    /// Skips `n` elements without building references for them and decodes the next one.
+    /// Jumps ahead with the checkpoints, or directly if the only type has a fixed size.
+    pub(crate) fn try_nth(&mut self, n: usize) -> Option<Result<BeadReference<'a>, BeadsError>> {
+        let target = self.index.saturating_add(n);
+        if target >= self.back {
+            self.index = self.back;
+            return None;
+        }
+        if let Some(position) = self.checkpoints.as_ref().and_then(|c| c.before(target)) {
+            if position.0 > self.index {
+                self.seek(position);
+            }
+        }
+        if self.type_count == 1 && self.types[0].data_size() != 255 {
+            let skipped = (target - self.index).saturating_mul(self.types[0].data_size() as usize);
+            self.data_cursor = self.data_cursor.saturating_add(skipped);
+            self.index = target;
+        }
+        while self.index < target {
+            if let Err(e) = self.read_position() {
+                self.exhaust();
+                return Some(Err(e));
+            }
+        }
+        self.try_next()
+    }
+
+    /// Decoder state before the next element: its index, the tag cursor and the data cursor.
+    pub(crate) fn position(&self) -> (usize, usize, usize) {
+        (self.index, self.tag_cursor, self.data_cursor)
+    }
+
+    /// Continues decoding from a state returned by `position`.
+    pub(crate) fn seek(&mut self, (index, tag_cursor, data_cursor): (usize, usize, usize)) {
+        self.index = index;
+        self.tag_cursor = tag_cursor;
+        self.data_cursor = data_cursor;
+    }
+
+    /// Number of bytes of the data section covered by the elements read so far.
+    pub(crate) fn consumed(&self) -> usize {
+        let tag_end = if self.type_count == 1 { 0 } else { self.tag_cursor + 1 };
+        max(max(self.data_cursor, tag_end), 1)
+    }
+
+    fn exhaust(&mut self) {
+        self.back = self.index;
+        self.back_block.clear();
+    }
+
+    /// Iterator over the same elements, positioned like this one, without the reverse state.
+    fn fork(&self) -> BeadsIterator<'a> {
+        let mut fork = BeadsIterator::new(self.buffer, self.back, &self.types[..self.type_count]);
+        fork.seek(self.position());
+        fork
+    }
+
+    /// Decodes the elements from the closest checkpoint up to `back`.
+    fn fill_back_block(&mut self) -> Result<(), BeadsError> {
+        if self.type_count == 1 && self.types[0].data_size() != 255 {
+            // the position of the last element can be computed, no need for checkpoints
+            let mut fork = self.fork();
+            self.back_block.extend(fork.try_nth(self.back - 1 - self.index).transpose()?);
+            return Ok(());
+        }
+        if self.checkpoints.is_none() {
+            // one pass from the front, remembering a position every few elements
+            let mut fork = self.fork();
+            let mut positions = vec![];
+            while fork.index < fork.back {
+                if fork.index - self.index == positions.len() * BACK_CHECKPOINT_STEP {
+                    positions.push(fork.position());
+                }
+                fork.read_position()?;
             }
+            self.checkpoints = Some(Checkpoints { first: self.index, step: BACK_CHECKPOINT_STEP, positions: Rc::new(positions) });
         }
+        let mut fork = self.fork();
+        if let Some(position) = self.checkpoints.as_ref().and_then(|c| c.before(self.back - 1)) {
+            if position.0 > self.index {
+                fork.seek(position);
+            }
+        }
+        while let Some(bead) = fork.try_next() {
+            self.back_block.push(bead?);
+        }
+        Ok(())
     }
 
-    fn get_type(&mut self) -> BeadType {
+    fn read_next(&mut self) -> Result<BeadReference<'a>, BeadsError> {
+        let (start, end, value, bead_type) = self.read_position()?;
+        Ok(BeadReference {
+            value,
+            buffer: self.buffer[start..end].as_ref(),
+            bead_type
+        })
+    }
+
+    /// Moves past the next element and returns where its payload is.
+    fn read_position(&mut self) -> Result<(usize, usize, u128, BeadType), BeadsError> {
+        let bead_type = if self.type_count == 1 {self.types[0]} else {self.get_type()?};
+        let tag_addition = if bead_type.has_no_data() { 0 } else { 1 };
+        let mut start = if self.type_count == 1 {self.data_cursor} else {max(self.data_cursor, self.tag_cursor + tag_addition)};
+        let (data_length, data_value) = Self::get_data_length_and_value(self.buffer.get(start..).unwrap_or(&[]), bead_type)
+            .map_err(|e| e.shift(start))?;
+        let mut end = start + data_length;
+        if bead_type == BeadType::Utf8 || bead_type == BeadType::Bytes {
+            start += data_length;
+            let length = if data_value > usize::MAX as u128 { usize::MAX } else { data_value as usize };
+            end = start.saturating_add(length);
+        }
+        if self.buffer.len() < end {
+            return Err(BeadsError::TruncatedBuffer { offset: start, needed: end - start });
+        }
+        self.data_cursor = end;
+        self.index += 1;
+        Ok((start, end, data_value, bead_type))
+    }
+
+    fn get_type(&mut self) -> Result<BeadType, BeadsError> {
         let tag_index = self.index % self.tags_per_byte;
         if self.index > 0 && tag_index == 0 {
             self.tag_cursor = max(self.tag_cursor + 1, self.data_cursor);
         }
-        let tag = self.buffer[self.tag_cursor];
+        let tag = *self.buffer.get(self.tag_cursor).ok_or(BeadsError::TruncatedBuffer { offset: self.tag_cursor, needed: 1 })?;
         let shift = tag_index * 8 / self.tags_per_byte;
         let mask = self.tag_mask << shift as u8;
         let type_index = ((tag & mask) as usize) >> shift;
-        self.types[type_index]
+        self.types[..self.type_count].get(type_index).copied().ok_or(BeadsError::InvalidTag {
+            offset: self.tag_cursor,
+            tag: type_index,
+            types: self.type_count
+        })
     }
 
-    fn get_data_length_and_value(buffer: &[u8], bead_type: BeadType) -> Result<(usize, u128), &'static str> {
-        return match bead_type {
+    pub(crate) fn get_data_length_and_value(buffer: &[u8], bead_type: BeadType) -> Result<(usize, u128), BeadsError> {
+        match bead_type {
             BeadType::None | BeadType::TrueFlag | BeadType::FalseFlag => Ok((0, 0)),
             BeadType::U8 | BeadType::I8 => Ok((1, 0)),
             BeadType::U16 | BeadType::I16 | BeadType::F16 => Ok((2, 0)),
             BeadType::U32 | BeadType::I32 | BeadType::F32 => Ok((4, 0)),
             BeadType::U64 | BeadType::I64 | BeadType::F64 => Ok((8, 0)),
-            BeadType::U128 | BeadType::I128 => Ok((8, 0)),
-            BeadType::Vlq | BeadType::VlqZ | BeadType::Utf8 | BeadType::Bytes => read_vlq(buffer).map(|v|v),
+            BeadType::U128 | BeadType::I128 => Ok((16, 0)),
+            BeadType::Vlq | BeadType::VlqZ | BeadType::Utf8 | BeadType::Bytes => read_vlq(buffer),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Iterator over the elements of an `IndexedBeads` sequence.
+pub struct IndexedBeadsIterator<'a> {
+    beads: IndexedBeads<'a>,
+    index: usize,
+    back: usize,
+}
+
+impl <'a> IndexedBeadsIterator<'a> {
+    pub(crate) fn new(beads: IndexedBeads<'a>) -> IndexedBeadsIterator<'a> {
+        IndexedBeadsIterator { index: 0, back: beads.len(), beads }
+    }
+
+    fn read(&mut self, index: usize) -> Option<&'a[u8]> {
+        let result = self.beads.get(index).ok();
+        if result.is_none() {
+            self.back = self.index;
+        }
+        result
+    }
+}
+
+impl <'a> Iterator for IndexedBeadsIterator<'a> {
+    type Item = &'a[u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.back {
+            return None;
+        }
+        self.index += 1;
+        self.read(self.index - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.index;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.index = std::cmp::min(self.index.saturating_add(n), self.back);
+        self.next()
+    }
+}
+
+impl ExactSizeIterator for IndexedBeadsIterator<'_> {}
+
+impl <'a> DoubleEndedIterator for IndexedBeadsIterator<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back <= self.index {
+            return None;
+        }
+        self.back -= 1;
+        self.read(self.back)
+    }
+}
+
+/// Iterator over the values of a `DedupBeads` sequence.
+pub struct DedupBeadsIterator<'a> {
+    beads: DedupBeads<'a>,
+    index: usize,
+    back: usize,
+}
+
+impl <'a> DedupBeadsIterator<'a> {
+    pub(crate) fn new(beads: DedupBeads<'a>) -> DedupBeadsIterator<'a> {
+        DedupBeadsIterator { index: 0, back: beads.len(), beads }
+    }
+
+    fn read(&mut self, index: usize) -> Option<&'a[u8]> {
+        let result = self.beads.get(index).ok();
+        if result.is_none() {
+            self.back = self.index;
+        }
+        result
+    }
+}
+
+impl <'a> Iterator for DedupBeadsIterator<'a> {
+    type Item = &'a[u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.back {
+            return None;
+        }
+        self.index += 1;
+        self.read(self.index - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.index;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.index = std::cmp::min(self.index.saturating_add(n), self.back);
+        self.next()
+    }
+}
+
+impl ExactSizeIterator for DedupBeadsIterator<'_> {}
+
+impl <'a> DoubleEndedIterator for DedupBeadsIterator<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back <= self.index {
+            return None;
+        }
+        self.back -= 1;
+        self.read(self.back)
+    }
+}
+
+/// Iterator over the values of a `DeltaBeads` sequence, adding up the stored differences.
+/// Stops at the first element which can't be decoded as int.
+pub struct DeltaBeadsIterator<'a> {
+    beads: BeadsIterator<'a>,
+    delta_of_delta: bool,
+    index: usize,
+    previous: i128,
+    delta: i128,
+}
+
+impl <'a> DeltaBeadsIterator<'a> {
+    pub(crate) fn new(beads: BeadsIterator<'a>, delta_of_delta: bool) -> DeltaBeadsIterator<'a> {
+        DeltaBeadsIterator { beads, delta_of_delta, index: 0, previous: 0, delta: 0 }
+    }
+
+    /// Decodes the next value, reporting why it could not be decoded instead of just stopping.
+    pub(crate) fn try_next(&mut self) -> Option<Result<i128, BeadsError>> {
+        let stored = match self.beads.try_next()?.and_then(|bead| bead.try_to_int()) {
+            Ok(stored) => stored,
+            Err(e) => {
+                self.beads.exhaust();
+                return Some(Err(e));
+            }
+        };
+        let value = match self.index {
+            0 => stored,
+            1 => {
+                self.delta = stored;
+                self.previous.wrapping_add(stored)
+            }
+            _ => {
+                self.delta = if self.delta_of_delta { self.delta.wrapping_add(stored) } else { stored };
+                self.previous.wrapping_add(self.delta)
+            }
+        };
+        self.previous = value;
+        self.index += 1;
+        Some(Ok(value))
+    }
+}
+
+impl <'a> Iterator for DeltaBeadsIterator<'a> {
+    type Item = i128;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.try_next()?.ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.beads.size_hint()
+    }
+}
+
+impl ExactSizeIterator for DeltaBeadsIterator<'_> {}
+
+/// Iterator over the elements of a `RleBeads` sequence, every value is returned as often as its run is long.
+pub struct RleBeadsIterator<'a> {
+    values: BeadsIterator<'a>,
+    lengths: BeadsIterator<'a>,
+    current: Option<BeadReference<'a>>,
+    // elements of the current run which were not returned yet
+    remaining: usize,
+    // elements of all runs which were not returned yet
+    left: usize,
+}
+
+impl <'a> RleBeadsIterator<'a> {
+    pub(crate) fn new(values: BeadsIterator<'a>, lengths: BeadsIterator<'a>, len: usize) -> RleBeadsIterator<'a> {
+        RleBeadsIterator { values, lengths, current: None, remaining: 0, left: len }
+    }
+
+    fn next_run(&mut self) -> Option<()> {
+        let run = self.lengths.next().and_then(|l| usize::try_from(l.try_to_uint().ok()?).ok());
+        self.current = self.values.next();
+        match (run, self.current) {
+            (Some(run), Some(_)) => {
+                self.remaining = run;
+                Some(())
+            }
+            _ => {
+                self.left = 0;
+                None
+            }
+        }
+    }
+}
+
+impl <'a> Iterator for RleBeadsIterator<'a> {
+    type Item = BeadReference<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.nth(0)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.left, Some(self.left))
+    }
+
+    /// Skips whole runs without returning their values.
+    fn nth(&mut self, mut n: usize) -> Option<Self::Item> {
+        if n >= self.left {
+            self.left = 0;
+            return None;
+        }
+        while n >= self.remaining {
+            n -= self.remaining;
+            self.left -= self.remaining;
+            self.next_run()?;
+        }
+        self.remaining -= n + 1;
+        self.left -= n + 1;
+        self.current
+    }
+}
+
+impl ExactSizeIterator for RleBeadsIterator<'_> {}
+
+/// Iterator over the values of a `BitPackedBeads` sequence. Values of up to 64 bits are shifted
+/// out of a buffer which is filled byte by byte, wider values are read one by one.
+/// Stops at the first value which does not fit into `u128` together with the reference.
+pub struct BitPackedBeadsIterator<'a> {
+    beads: BitPackedBeads<'a>,
+    index: usize,
+    // next byte to be moved into `pending`
+    byte: usize,
+    pending: u128,
+    pending_bits: usize,
+}
+
+impl <'a> BitPackedBeadsIterator<'a> {
+    pub(crate) fn new(beads: BitPackedBeads<'a>) -> BitPackedBeadsIterator<'a> {
+        BitPackedBeadsIterator { beads, index: 0, byte: 0, pending: 0, pending_bits: 0 }
+    }
+
+    fn next_delta(&mut self) -> u128 {
+        let bits = self.beads.bits();
+        if bits > 64 {
+            return self.beads.delta(self.index);
+        }
+        let data = self.beads.data();
+        while self.pending_bits < bits {
+            self.pending |= (data[self.byte] as u128) << self.pending_bits;
+            self.byte += 1;
+            self.pending_bits += 8;
+        }
+        let delta = self.beads.mask(self.pending);
+        self.pending >>= bits;
+        self.pending_bits -= bits;
+        delta
+    }
+}
+
+impl <'a> Iterator for BitPackedBeadsIterator<'a> {
+    type Item = u128;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.beads.len() {
+            return None;
+        }
+        let delta = self.next_delta();
+        self.index += 1;
+        let value = self.beads.with_reference(delta).ok();
+        if value.is_none() {
+            self.index = self.beads.len();
+        }
+        value
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.beads.len() - self.index;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for BitPackedBeadsIterator<'_> {}
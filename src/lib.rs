@@ -1,10 +1,23 @@
 mod vlq;
+pub mod error;
 pub mod bead_type;
+pub mod decimal;
 pub mod builder;
 pub mod iterator;
 pub mod reference;
 pub mod sequence;
 pub mod converters;
+pub mod columns;
+pub mod reader;
+pub mod analyzer;
+pub mod stats;
+#[cfg(feature = "serde")]
+pub mod serde_beads;
 
+#[cfg(feature = "derive")]
+pub use rusty_beads_derive::BeadsColumns;
+
+#[cfg(test)]
+mod tests;
 #[cfg(test)]
-mod tests;
\ No newline at end of file
+mod fuzz_tests;
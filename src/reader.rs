@@ -0,0 +1,358 @@
+use std::cmp::max;
+use std::convert::TryFrom;
+use std::io;
+use crate::bead_type::{BeadType, BeadTypeSet, TimeKind};
+use crate::error::BeadsError;
+use crate::iterator::BeadsIterator;
+use crate::reference::{BeadReference, OwnedBead};
+use crate::vlq::read_vlq;
+
+const READ_CHUNK_SIZE: usize = 8192;
+const MAX_VLQ_SIZE: usize = 19;
+
+/// Decodes a typed Beads sequence from an `io::Read`, without loading it into memory.
+///
+/// Bytes are read in chunks of 8 KiB and dropped as soon as the elements they belong to are
+/// decoded, so only the current element (and a chunk of read ahead) is kept in memory.
+/// `next_bead` borrows each element from the internal buffer until the next call,
+/// the `Iterator` implementation yields owned copies.
+pub struct BeadsReader<R> where R: io::Read {
+    reader: R,
+    buffer: Vec<u8>,
+    // number of bytes of the buffer which hold read data
+    filled: usize,
+    // data section offset of buffer[0]
+    base: usize,
+    header_size: usize,
+    types: Vec<BeadType>,
+    time: Option<TimeKind>,
+    scale: Option<u8>,
+    count: usize,
+    index: usize,
+    tag: u8,
+    tag_cursor: usize,
+    data_cursor: usize,
+    tags_per_byte: usize,
+    tag_mask: u8,
+}
+
+impl<R> BeadsReader<R> where R: io::Read {
+    pub fn new(reader: R, types: &BeadTypeSet) -> Result<BeadsReader<R>, BeadsError> {
+        let time = types.time();
+        let scale = types.scale();
+        let types = types.types_by_priority()?;
+        let mut result = BeadsReader {
+            reader,
+            buffer: vec![],
+            filled: 0,
+            base: 0,
+            header_size: 0,
+            count: 0,
+            index: 0,
+            tag: 0,
+            tag_cursor: 0,
+            data_cursor: 0,
+            tag_mask: match types.len() {
+                1..=2 => 1,
+                3..=4 => 3,
+                _ => 15
+            },
+            tags_per_byte: match types.len() {
+                1..=2 => 8,
+                3..=4 => 4,
+                _ => 2
+            },
+            types,
+            time,
+            scale,
+        };
+        result.fill(MAX_VLQ_SIZE)?;
+        let (vlq_len, count) = read_vlq(&result.buffer[..result.filled])?;
+        result.count = usize::try_from(count).unwrap_or(usize::MAX);
+        result.header_size = vlq_len;
+        result.discard(vlq_len);
+        Ok(result)
+    }
+
+    /// Same as `new` for a sequence which starts with its type set.
+    pub fn new_types_included(mut reader: R) -> Result<BeadsReader<R>, BeadsError> {
+        let mut types = [0; 4];
+        reader.read_exact(&mut types).map_err(|e| {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                BeadsError::TruncatedBuffer { offset: 0, needed: 4 }
+            } else {
+                BeadsError::Io(e)
+            }
+        })?;
+        let types = BeadTypeSet::from(u32::from_le_bytes(types));
+        let mut result = Self::new(reader, &types).map_err(|e| e.shift(4))?;
+        result.header_size += 4;
+        Ok(result)
+    }
+
+    /// Number of elements in the sequence.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the values are timestamps or durations and in which unit, see `BeadTypeSet::with_time`.
+    pub fn time(&self) -> Option<TimeKind> {
+        self.time
+    }
+
+    /// The number of fraction digits, if the values are decimals, see `BeadTypeSet::with_scale`.
+    pub fn scale(&self) -> Option<u8> {
+        self.scale
+    }
+
+    /// Decodes the next element. The reference points into the internal buffer,
+    /// so it has to be dropped (or copied with `to_owned_bead`) before the next call.
+    /// After an error the reader is exhausted. Offsets in errors are relative to the start of the stream.
+    pub fn next_bead(&mut self) -> Option<Result<BeadReference<'_>, BeadsError>> {
+        if self.index >= self.count {
+            return None;
+        }
+        match self.read_next() {
+            Ok((start, end, value, bead_type)) => Some(Ok(BeadReference {
+                value,
+                buffer: &self.buffer[start - self.base..end - self.base],
+                bead_type
+            })),
+            Err(e) => {
+                self.index = self.count;
+                Some(Err(e.shift(self.header_size)))
+            }
+        }
+    }
+
+    fn read_next(&mut self) -> Result<(usize, usize, u128, BeadType), BeadsError> {
+        // everything before the data cursor belongs to elements which were already returned
+        let consumed = std::cmp::min(self.data_cursor - self.base, self.filled);
+        if consumed >= READ_CHUNK_SIZE {
+            self.discard(consumed);
+            self.base += consumed;
+        }
+
+        let bead_type = if self.types.len() == 1 {self.types[0]} else {self.get_type()?};
+        let tag_addition = if bead_type.has_no_data() { 0 } else { 1 };
+        let mut start = if self.types.len() == 1 {self.data_cursor} else {max(self.data_cursor, self.tag_cursor + tag_addition)};
+        let (data_length, data_value) = if bead_type.has_no_data() {
+            (0, 0)
+        } else {
+            self.fill(start + MAX_VLQ_SIZE)?;
+            let available = self.buffer[..self.filled].get(start - self.base..).unwrap_or(&[]);
+            BeadsIterator::get_data_length_and_value(available, bead_type).map_err(|e| e.shift(start))?
+        };
+        let mut end = start + data_length;
+        if bead_type == BeadType::Utf8 || bead_type == BeadType::Bytes {
+            start += data_length;
+            let length = if data_value > usize::MAX as u128 { usize::MAX } else { data_value as usize };
+            end = start.saturating_add(length);
+        }
+        if !self.fill(end)? {
+            return Err(BeadsError::TruncatedBuffer { offset: start, needed: end - start });
+        }
+        self.data_cursor = end;
+        self.index += 1;
+        Ok((start, end, data_value, bead_type))
+    }
+
+    fn get_type(&mut self) -> Result<BeadType, BeadsError> {
+        let tag_index = self.index % self.tags_per_byte;
+        if self.index == 0 || tag_index == 0 {
+            if self.index > 0 {
+                self.tag_cursor = max(self.tag_cursor + 1, self.data_cursor);
+            }
+            if !self.fill(self.tag_cursor + 1)? {
+                return Err(BeadsError::TruncatedBuffer { offset: self.tag_cursor, needed: 1 });
+            }
+            self.tag = self.buffer[self.tag_cursor - self.base];
+        }
+        let shift = tag_index * 8 / self.tags_per_byte;
+        let mask = self.tag_mask << shift as u8;
+        let type_index = ((self.tag & mask) as usize) >> shift;
+        self.types.get(type_index).copied().ok_or(BeadsError::InvalidTag {
+            offset: self.tag_cursor,
+            tag: type_index,
+            types: self.types.len()
+        })
+    }
+
+    fn discard(&mut self, count: usize) {
+        self.buffer.copy_within(count..self.filled, 0);
+        self.filled -= count;
+    }
+
+    /// Reads until the buffer covers the data section up to `end`. Returns `false` if the stream ends before.
+    fn fill(&mut self, end: usize) -> Result<bool, BeadsError> {
+        while self.base + self.filled < end {
+            if self.filled == self.buffer.len() {
+                let missing = end - self.base - self.filled;
+                // a huge length read from a broken stream must not allocate everything up front,
+                // so the buffer grows at most by the bytes it already holds, which keeps growing amortised
+                let grow = missing.clamp(READ_CHUNK_SIZE, max(self.filled, READ_CHUNK_SIZE));
+                self.buffer.resize(self.filled + grow, 0);
+            }
+            let read = match self.reader.read(&mut self.buffer[self.filled..]) {
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into())
+            };
+            if read == 0 {
+                return Ok(false);
+            }
+            self.filled += read;
+        }
+        Ok(true)
+    }
+}
+
+impl<R> Iterator for BeadsReader<R> where R: io::Read {
+    type Item = Result<OwnedBead, BeadsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_bead().map(|r| r.map(|b| b.to_owned_bead()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use crate::bead_type::{BeadTypeSet, BeadType};
+    use crate::builder::TypedBeadsBuilder;
+    use crate::error::BeadsError;
+    use crate::reader::{BeadsReader, READ_CHUNK_SIZE};
+    use crate::sequence::TypedBeads;
+
+    // hands out the data in tiny pieces, like a pipe would
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize
+    }
+
+    impl io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = std::cmp::min(std::cmp::min(self.step, buf.len()), self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    fn describe(bead: crate::reference::BeadReference) -> String {
+        if bead.is_none() {
+            "None".to_string()
+        } else if bead.is_bool() {
+            bead.to_bool().to_string()
+        } else if bead.is_string() {
+            bead.to_str().to_string()
+        } else if bead.is_uint() {
+            bead.to_uint().to_string()
+        } else if bead.is_int() {
+            bead.to_int().to_string()
+        } else if bead.is_float() {
+            bead.to_float().to_string()
+        } else {
+            format!("{:?}", bead.to_bytes())
+        }
+    }
+
+    #[test]
+    fn reads_the_same_values_as_typed_beads() {
+        let type_sets = vec![
+            BeadTypeSet::new(&[BeadType::Utf8]),
+            BeadTypeSet::new(&[BeadType::U8, BeadType::None]),
+            BeadTypeSet::new(&[BeadType::U8, BeadType::I16, BeadType::None, BeadType::Utf8]),
+            BeadTypeSet::new(&[BeadType::U8, BeadType::I16, BeadType::None, BeadType::Utf8, BeadType::F32, BeadType::TrueFlag, BeadType::Bytes]),
+        ];
+        for types in type_sets {
+            let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+            for i in 0..5_000i128 {
+                match i % 6 {
+                    0 => builder.push_none(),
+                    1 => builder.push_int(-i % 300),
+                    2 => builder.push_string(&"x".repeat((i % 40) as usize)),
+                    3 => builder.push_bool(true),
+                    4 => builder.push_bytes(&[1, 2, 3]),
+                    _ => builder.push_double(0.5),
+                };
+            }
+            let mut buffer = vec![];
+            builder.encode(&mut buffer).unwrap();
+            let beads = TypedBeads::new(&buffer, &types).ok().unwrap();
+            let expected: Vec<String> = beads.iter().map(describe).collect();
+
+            for step in [1, 7, 100_000] {
+                let mut reader = BeadsReader::new(Trickle { data: &buffer, step }, &types).ok().unwrap();
+                assert_eq!(reader.len(), beads.len());
+                let mut values = vec![];
+                while let Some(bead) = reader.next_bead() {
+                    values.push(describe(bead.ok().unwrap()));
+                }
+                assert_eq!(values, expected);
+                assert!(reader.buffer.len() <= 2 * super::READ_CHUNK_SIZE);
+            }
+
+            let owned: Vec<String> = BeadsReader::new(buffer.as_slice(), &types).ok().unwrap()
+                .map(|b| describe(b.ok().unwrap().as_bead())).collect();
+            assert_eq!(owned, expected);
+        }
+    }
+
+    #[test]
+    fn reads_types_included() {
+        let types = BeadTypeSet::new(&[BeadType::U8, BeadType::Utf8]);
+        let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+        builder.push_string("Maxim");
+        builder.push_uint(42);
+        let mut buffer = vec![];
+        builder.encode_with_types(&mut buffer).unwrap();
+
+        let values: Vec<String> = BeadsReader::new_types_included(buffer.as_slice()).ok().unwrap()
+            .map(|b| describe(b.ok().unwrap().as_bead())).collect();
+        assert_eq!(values, vec!["Maxim", "42"]);
+    }
+
+    #[test]
+    fn reports_truncated_stream() {
+        let types = BeadTypeSet::new(&[BeadType::U8, BeadType::Utf8]);
+        let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+        builder.push_uint(1);
+        builder.push_string("Maxim");
+        let mut buffer = vec![];
+        builder.encode(&mut buffer).unwrap();
+        buffer.truncate(buffer.len() - 2);
+
+        let mut reader = BeadsReader::new(buffer.as_slice(), &types).ok().unwrap();
+        assert_eq!(reader.next().unwrap().ok().unwrap().as_bead().to_uint(), 1);
+        let error = reader.next().unwrap().err().unwrap();
+        assert!(matches!(error, BeadsError::TruncatedBuffer { offset: 4, needed: 5 }));
+        assert!(reader.next().is_none());
+
+        assert!(matches!(BeadsReader::new(&[][..], &types).err().unwrap(), BeadsError::InvalidVlq { .. }));
+        assert!(matches!(BeadsReader::new_types_included(&[1, 0][..]).err().unwrap(), BeadsError::TruncatedBuffer { .. }));
+    }
+
+    #[test]
+    fn reads_large_values_and_bounds_the_buffer() {
+        let types = BeadTypeSet::new(&[BeadType::Bytes]);
+        let value: Vec<u8> = (0..3_000_000u32).map(|i| (i % 251) as u8).collect();
+        let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+        builder.push_bytes(&value);
+        let mut buffer = vec![];
+        builder.encode(&mut buffer).unwrap();
+
+        let mut reader = BeadsReader::new(Trickle { data: &buffer, step: 100_000 }, &types).ok().unwrap();
+        assert_eq!(reader.next_bead().unwrap().ok().unwrap().to_bytes(), value.as_slice());
+        assert!(reader.buffer.len() < 2 * value.len());
+
+        // the length claims 8 GiB, but the stream ends after a few bytes
+        let mut reader = BeadsReader::new(&[1, 0x80, 0x80, 0x80, 0x80, 0x20, 1, 2, 3][..], &types).ok().unwrap();
+        assert!(matches!(reader.next().unwrap().err().unwrap(), BeadsError::TruncatedBuffer { .. }));
+        assert!(reader.buffer.len() <= 2 * READ_CHUNK_SIZE);
+    }
+}
@@ -1,15 +1,44 @@
-use crate::bead_type::BeadType;
+use crate::bead_type::{BeadType, BeadTypeSet, TimeUnit};
 use crate::vlq::zigzag_decode;
 use half::f16;
 use std::convert::TryFrom;
+use crate::error::BeadsError;
+use crate::decimal::Decimal;
+use crate::sequence::{TypedBeads, IndexedBeads, FixedSizeBeads};
+use std::time::{Duration, SystemTime};
 
+#[derive(Clone, Copy)]
 pub struct BeadReference<'a> {
     pub(crate) value: u128,
     pub(crate) buffer: &'a[u8],
     pub(crate) bead_type: BeadType
 }
 
+/// Owned copy of a bead, for values which have to outlive the buffer they were read from.
+pub struct OwnedBead {
+    value: u128,
+    buffer: Vec<u8>,
+    bead_type: BeadType
+}
+
+impl OwnedBead {
+    pub fn as_bead(&self) -> BeadReference<'_> {
+        BeadReference {
+            value: self.value,
+            buffer: self.buffer.as_slice(),
+            bead_type: self.bead_type
+        }
+    }
+}
+
 impl<'a> BeadReference<'a> {
+    pub fn to_owned_bead(&self) -> OwnedBead {
+        OwnedBead {
+            value: self.value,
+            buffer: self.buffer.to_vec(),
+            bead_type: self.bead_type
+        }
+    }
     pub fn is_none(&self) -> bool {
         self.bead_type == BeadType::None
     }
@@ -50,77 +79,186 @@ impl<'a> BeadReference<'a> {
         self.bead_type == BeadType::Utf8
     }
     pub fn to_bool(&self) -> bool {
+        self.try_to_bool().unwrap_or_else(|e| panic!("{}", e))
+    }
+    pub fn to_str(&self) -> &'a str {
+        self.try_to_str().unwrap_or_else(|e| panic!("{}", e))
+    }
+    pub fn to_bytes(&self) -> &'a[u8] {
+        self.buffer
+    }
+    pub fn to_uint(&self) -> u128 {
+        self.try_to_uint().unwrap_or_else(|e| panic!("{}", e))
+    }
+    pub fn to_int(&self) -> i128 {
+        self.try_to_int().unwrap_or_else(|e| panic!("{}", e))
+    }
+    pub fn to_float(&self) -> f64 {
+        self.try_to_float().unwrap_or_else(|e| panic!("{}", e))
+    }
+    pub fn to_decimal(&self, scale: u8) -> Decimal {
+        self.try_to_decimal(scale).unwrap_or_else(|e| panic!("{}", e))
+    }
+    pub fn to_timestamp(&self, unit: TimeUnit) -> SystemTime {
+        self.try_to_timestamp(unit).unwrap_or_else(|e| panic!("{}", e))
+    }
+    pub fn to_duration(&self, unit: TimeUnit) -> Duration {
+        self.try_to_duration(unit).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_to_bool(&self) -> Result<bool, BeadsError> {
         if self.is_bool() {
-            self.bead_type == BeadType::TrueFlag
+            Ok(self.bead_type == BeadType::TrueFlag)
         } else {
-            panic!("Not a bool value")
+            Err(BeadsError::TypeMismatch { found: self.bead_type, expected: "bool" })
         }
     }
-    pub fn to_str(&self) -> &'a str {
+
+    pub fn try_to_str(&self) -> Result<&'a str, BeadsError> {
         if self.is_string() {
-            std::str::from_utf8(self.buffer).expect("value is not string")
+            std::str::from_utf8(self.buffer).map_err(|e| BeadsError::InvalidUtf8 { offset: e.valid_up_to() })
         } else {
-            panic!("value is not string")
+            Err(BeadsError::TypeMismatch { found: self.bead_type, expected: "string" })
         }
     }
-    pub fn to_bytes(&self) -> &'a[u8] {
-        self.buffer
-    }
-    pub fn to_uint(&self) -> u128 {
-        return match self.bead_type {
-            BeadType::Vlq => self.value,
-            BeadType::U8 => self.buffer[0] as u128,
-            BeadType::U16 => u16::from_le_bytes(Self::clone_into_array(self.buffer)) as u128,
-            BeadType::U32 => u32::from_le_bytes(Self::clone_into_array(self.buffer)) as u128,
-            BeadType::U64 => u64::from_le_bytes(Self::clone_into_array(self.buffer)) as u128,
-            BeadType::U128 => u128::from_le_bytes(Self::clone_into_array(self.buffer)) as u128,
+
+    pub fn try_to_uint(&self) -> Result<u128, BeadsError> {
+        match self.bead_type {
+            BeadType::Vlq => Ok(self.value),
+            BeadType::U8 => Ok(u8::from_le_bytes(self.le_bytes()?) as u128),
+            BeadType::U16 => Ok(u16::from_le_bytes(self.le_bytes()?) as u128),
+            BeadType::U32 => Ok(u32::from_le_bytes(self.le_bytes()?) as u128),
+            BeadType::U64 => Ok(u64::from_le_bytes(self.le_bytes()?) as u128),
+            BeadType::U128 => Ok(u128::from_le_bytes(self.le_bytes()?)),
             _ => if self.is_int() {
-                let int = self.to_int();
+                let int = self.try_to_int()?;
                 if int >= 0  {
-                    int as u128
+                    Ok(int as u128)
                 } else {
-                    panic!("Is a negative int value")
+                    Err(BeadsError::OutOfRange { value: int.to_string(), target: "u128" })
                 }
             } else {
-                panic!("Not an int type")
+                Err(BeadsError::TypeMismatch { found: self.bead_type, expected: "uint" })
             }
         }
     }
 
-    pub fn to_int(&self) -> i128 {
-        return match self.bead_type {
-            BeadType::VlqZ => zigzag_decode(self.value),
-            BeadType::I8 => i8::from_le_bytes(Self::clone_into_array(self.buffer)) as i128,
-            BeadType::I16 => i16::from_le_bytes(Self::clone_into_array(self.buffer)) as i128,
-            BeadType::I32 => i32::from_le_bytes(Self::clone_into_array(self.buffer)) as i128,
-            BeadType::I64 => i64::from_le_bytes(Self::clone_into_array(self.buffer)) as i128,
-            BeadType::I128 => i128::from_le_bytes(Self::clone_into_array(self.buffer)) as i128,
+    pub fn try_to_int(&self) -> Result<i128, BeadsError> {
+        match self.bead_type {
+            BeadType::VlqZ => Ok(zigzag_decode(self.value)),
+            BeadType::I8 => Ok(i8::from_le_bytes(self.le_bytes()?) as i128),
+            BeadType::I16 => Ok(i16::from_le_bytes(self.le_bytes()?) as i128),
+            BeadType::I32 => Ok(i32::from_le_bytes(self.le_bytes()?) as i128),
+            BeadType::I64 => Ok(i64::from_le_bytes(self.le_bytes()?) as i128),
+            BeadType::I128 => Ok(i128::from_le_bytes(self.le_bytes()?)),
             _ => if self.is_uint() {
-                let uint = self.to_uint();
-                if uint <= std::i128::MAX as u128  {
-                    uint as i128
+                let uint = self.try_to_uint()?;
+                if uint <= i128::MAX as u128  {
+                    Ok(uint as i128)
                 } else {
-                    panic!("Is a very high uint value")
+                    Err(BeadsError::OutOfRange { value: uint.to_string(), target: "i128" })
                 }
             } else {
-                panic!("Not an int type")
+                Err(BeadsError::TypeMismatch { found: self.bead_type, expected: "int" })
             }
         }
     }
 
-    pub fn to_float(&self) -> f64 {
-        return match self.bead_type {
-            BeadType::F16 => f16::from_bits(u16::from_le_bytes(Self::clone_into_array(self.buffer))).to_f64(),
-            BeadType::F32 => f32::from_le_bytes(Self::clone_into_array(self.buffer)) as f64,
-            BeadType::F64 => f64::from_le_bytes(Self::clone_into_array(self.buffer)) as f64,
-            _ => if self.is_int() || self.is_uint() {
-                self.to_int() as f64
+    pub fn try_to_float(&self) -> Result<f64, BeadsError> {
+        match self.bead_type {
+            BeadType::F16 => Ok(f16::from_bits(u16::from_le_bytes(self.le_bytes()?)).to_f64()),
+            BeadType::F32 => Ok(f32::from_le_bytes(self.le_bytes()?) as f64),
+            BeadType::F64 => Ok(f64::from_le_bytes(self.le_bytes()?)),
+            _ => if self.is_int() {
+                Ok(self.try_to_int()? as f64)
+            } else if self.is_uint() {
+                Ok(self.try_to_uint()? as f64)
             } else {
-                panic!("Not an int type")
+                Err(BeadsError::TypeMismatch { found: self.bead_type, expected: "float" })
             }
         }
     }
 
+    /// The value pushed with `TypedBeadsBuilder::push_beads` as a `TypedBeads` sequence.
+    /// Offsets in errors are relative to the start of the value.
+    pub fn as_typed_beads(&self, types: &BeadTypeSet) -> Result<TypedBeads<'a>, BeadsError> {
+        TypedBeads::new(self.nested()?, types)
+    }
+
+    /// Same as `as_typed_beads`, for a value written with `encode_with_types`.
+    pub fn as_typed_beads_types_included(&self) -> Result<TypedBeads<'a>, BeadsError> {
+        TypedBeads::new_types_included(self.nested()?)
+    }
+
+    pub fn as_indexed_beads(&self) -> Result<IndexedBeads<'a>, BeadsError> {
+        IndexedBeads::new(self.nested()?)
+    }
+
+    pub fn as_fixed_size_beads(&self) -> Result<FixedSizeBeads<'a>, BeadsError> {
+        FixedSizeBeads::new(self.nested()?)
+    }
+
+    fn nested(&self) -> Result<&'a[u8], BeadsError> {
+        if self.is_bytes() {
+            Ok(self.buffer)
+        } else {
+            Err(BeadsError::TypeMismatch { found: self.bead_type, expected: "nested sequence" })
+        }
+    }
+
+    /// Reads an int or uint value as the mantissa of a decimal with `scale` fraction digits,
+    /// use the scale of the sequence, like `TypedBeads::get_decimal` does.
+    pub fn try_to_decimal(&self, scale: u8) -> Result<Decimal, BeadsError> {
+        if !self.is_int() && !self.is_uint() {
+            return Err(BeadsError::TypeMismatch { found: self.bead_type, expected: "decimal" });
+        }
+        Decimal::new(self.try_to_int()?, scale)
+    }
+
+    /// Reads an int or uint value as `unit`s since the Unix epoch.
+    pub fn try_to_timestamp(&self, unit: TimeUnit) -> Result<SystemTime, BeadsError> {
+        if !self.is_int() && !self.is_uint() {
+            return Err(BeadsError::TypeMismatch { found: self.bead_type, expected: "timestamp" });
+        }
+        unit.timestamp(self.try_to_int()?)
+    }
+
+    /// Reads a not negative int or uint value as a number of `unit`s.
+    pub fn try_to_duration(&self, unit: TimeUnit) -> Result<Duration, BeadsError> {
+        if !self.is_int() && !self.is_uint() {
+            return Err(BeadsError::TypeMismatch { found: self.bead_type, expected: "duration" });
+        }
+        unit.duration(self.try_to_uint()?)
+    }
+
+    fn le_bytes<A>(&self) -> Result<A, BeadsError> where A: Sized + Default + AsMut<[u8]> {
+        let mut a: A = Default::default();
+        let size = a.as_mut().len();
+        if self.buffer.len() < size {
+            return Err(BeadsError::TruncatedBuffer { offset: 0, needed: size });
+        }
+        a.as_mut().copy_from_slice(&self.buffer[..size]);
+        Ok(a)
+    }
+
+    /// The value as text for error messages, without failing on values which can't be decoded.
+    pub(crate) fn describe(&self) -> String {
+        let value = if self.is_none() {
+            Ok("None".to_string())
+        } else if self.is_bool() {
+            self.try_to_bool().map(|v| v.to_string())
+        } else if self.is_uint() {
+            self.try_to_uint().map(|v| v.to_string())
+        } else if self.is_int() {
+            self.try_to_int().map(|v| v.to_string())
+        } else if self.is_float() {
+            self.try_to_float().map(|v| v.to_string())
+        } else {
+            self.try_to_str().map(|v| format!("'{}'", v))
+        };
+        value.unwrap_or_else(|_| format!("{:?}", self.buffer))
+    }
+
     pub(crate) fn clone_into_array<A, T>(slice: &[T]) -> A
         where A: Sized + Default + AsMut<[T]>,
               T: Clone
@@ -135,14 +273,16 @@ macro_rules! try_from_int {
     ( $( $x:ident ),* ) => {
         $(
 impl TryFrom<BeadReference<'_>> for $x {
-    type Error = ();
+    type Error = BeadsError;
     fn try_from(value: BeadReference) -> Result<Self, Self::Error> {
         if value.is_uint() {
-            <$x as TryFrom<u128>>::try_from(value.to_uint()).map_err(|_| ())
+            let v = value.try_to_uint()?;
+            <$x as TryFrom<u128>>::try_from(v).map_err(|_| BeadsError::OutOfRange { value: v.to_string(), target: stringify!($x) })
         } else if value.is_int() {
-            <$x as TryFrom<i128>>::try_from(value.to_int()).map_err(|_| ())
+            let v = value.try_to_int()?;
+            <$x as TryFrom<i128>>::try_from(v).map_err(|_| BeadsError::OutOfRange { value: v.to_string(), target: stringify!($x) })
         } else {
-            Err(())
+            Err(BeadsError::TypeMismatch { found: value.bead_type, expected: stringify!($x) })
         }
     }
 }
@@ -150,37 +290,25 @@ impl TryFrom<BeadReference<'_>> for $x {
     }
 }
 
-try_from_int![u8, u16, u32, u64, u128, i8, i16, i32, i64, i128];
+try_from_int![u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize];
 
 impl TryFrom<BeadReference<'_>> for String {
-    type Error = ();
+    type Error = BeadsError;
     fn try_from(value: BeadReference) -> Result<Self, Self::Error> {
-        if value.is_string() {
-            Ok(String::from(value.to_str()))
-        } else {
-            Err(())
-        }
+        value.try_to_str().map(String::from)
     }
 }
 
 impl TryFrom<BeadReference<'_>> for f32 {
-    type Error = ();
+    type Error = BeadsError;
     fn try_from(value: BeadReference) -> Result<Self, Self::Error> {
-        if value.is_float() || value.is_int() || value.is_uint() {
-            Ok(value.to_float() as f32)
-        } else {
-            Err(())
-        }
+        value.try_to_float().map(|v| v as f32)
     }
 }
 
 impl TryFrom<BeadReference<'_>> for f64 {
-    type Error = ();
+    type Error = BeadsError;
     fn try_from(value: BeadReference) -> Result<Self, Self::Error> {
-        if value.is_float() || value.is_int() || value.is_uint() {
-            Ok(value.to_float())
-        } else {
-            Err(())
-        }
+        value.try_to_float()
     }
-}
\ No newline at end of file
+}
@@ -1,56 +1,296 @@
-use crate::iterator::BeadsIterator;
-use crate::bead_type::{BeadType, BeadTypeSet};
-use crate::vlq::read_vlq;
+use crate::iterator::{BeadsIterator, IndexedBeadsIterator, DedupBeadsIterator, DeltaBeadsIterator, RleBeadsIterator, BitPackedBeadsIterator, Checkpoints};
+use crate::bead_type::{BeadType, BeadTypeSet, TimeKind};
+use crate::vlq::{read_vlq, add_as_vlq};
 use std::borrow::Borrow;
 use crate::reference::BeadReference;
-use std::ops::Index;
+use std::ops::{Index, RangeBounds, Bound};
 use crate::converters::u128_from_slice;
+use crate::error::BeadsError;
+use std::convert::TryFrom;
+use std::cmp::max;
+use std::rc::Rc;
+use std::mem;
+use std::io;
+use crate::builder::TypedBeadsBuilder;
+use crate::stats::BeadsStats;
+use crate::decimal::Decimal;
 
 pub struct TypedBeads<'a> {
     buffer: &'a[u8],
+    header_size: usize,
     count: usize,
     types: Vec<BeadType>,
+    time: Option<TimeKind>,
+    scale: Option<u8>,
+    seek_index: Option<Checkpoints>,
 }
 
 impl<'a> TypedBeads<'a> {
-    pub fn new(buffer: &'a[u8], types: &'_ BeadTypeSet) -> Result<TypedBeads<'a>, &'static str> {
-        if types.size() < 1 || types.size() > 16 {
-            return Err("Beads sequence can carry only 1..=16 types");
-        }
-        let mut _types = vec![];
-        for t in BeadType::cases_by_priority() {
-            if types.contains(&t) {
-                _types.push(t)
-            }
-        }
-
+    pub fn new(buffer: &'a[u8], types: &'_ BeadTypeSet) -> Result<TypedBeads<'a>, BeadsError> {
+        let _types = types.types_by_priority()?;
         let (vlq_len, count) = read_vlq(buffer)?;
 
         if buffer.len() <= vlq_len {
-            return Err("Buffer is invalid due to its insufficient length");
+            return Err(BeadsError::TruncatedBuffer { offset: vlq_len, needed: 1 });
         }
 
         Ok(TypedBeads {
             buffer: buffer[vlq_len..].as_ref(),
+            header_size: vlq_len,
             count: count as usize,
-            types: _types
+            types: _types,
+            time: types.time(),
+            scale: types.scale(),
+            seek_index: None
         })
     }
 
-    pub fn new_types_included(buffer: &'a[u8]) -> Result<TypedBeads<'a>, &'static str> {
+    pub fn new_types_included(buffer: &'a[u8]) -> Result<TypedBeads<'a>, BeadsError> {
         if buffer.len() < 5 {
-            return Err("Buffer can't be a valid TypedBead including types as it is too small");
+            return Err(BeadsError::TruncatedBuffer { offset: 0, needed: 5 });
         }
         let types_value = u32::from_le_bytes(BeadReference::clone_into_array(&buffer[..4]));
-        Self::new(&buffer[4..], (BeadTypeSet::from(types_value)).borrow())
+        let mut beads = Self::new(&buffer[4..], (BeadTypeSet::from(types_value)).borrow()).map_err(|e| e.shift(4))?;
+        beads.header_size += 4;
+        Ok(beads)
     }
 
-    pub fn iter(&self) -> BeadsIterator {
-        BeadsIterator::new(self.buffer, self.count, self.types.borrow())
+    /// Iterates over the elements. Skipping with `nth` and reverse iteration use the seek index,
+    /// if one was built. The iterator stops at an element which can't be decoded, so call `validate`
+    /// first if the buffer comes from an untrusted source and its length has to be exact.
+    pub fn iter(&self) -> BeadsIterator<'a> {
+        BeadsIterator::new(self.buffer, self.count, self.types.borrow()).with_checkpoints(self.seek_index.clone())
     }
 
     pub fn len(&self) -> usize {self.count}
 
+    pub fn is_empty(&self) -> bool { self.count == 0 }
+
+    /// Whether the values are timestamps or durations and in which unit, see `BeadTypeSet::with_time`.
+    pub fn time(&self) -> Option<TimeKind> {
+        self.time
+    }
+
+    /// The number of fraction digits, if the values are decimals, see `BeadTypeSet::with_scale`.
+    pub fn scale(&self) -> Option<u8> {
+        self.scale
+    }
+
+    /// Reads element `index` as a decimal of the scale of the sequence.
+    /// Fails if the sequence was not declared with a scale.
+    pub fn get_decimal(&self, index: usize) -> Result<Decimal, BeadsError> {
+        let scale = self.scale.ok_or(BeadsError::MissingScale)?;
+        self.get(index)?.try_to_decimal(scale)
+    }
+
+    /// Walks the sequence once and remembers the decoder position of every `step`-th element,
+    /// so `get` has to decode at most `step` elements. Fails if an element can't be decoded.
+    pub fn build_seek_index(&mut self, step: usize) -> Result<(), BeadsError> {
+        let step = max(step, 1);
+        let mut positions = vec![];
+        if self.types.len() > 1 || !self.types[0].has_no_data() {
+            let mut iter = self.iter();
+            let mut index = 0;
+            while index < self.count {
+                if index % step == 0 {
+                    positions.push(iter.position());
+                }
+                if let Some(Err(e)) = iter.try_next() {
+                    return Err(BeadsError::InvalidElement { index, error: Box::new(e.shift(self.header_size)) });
+                }
+                index += 1;
+            }
+        }
+        self.seek_index = Some(Checkpoints { first: 0, step, positions: Rc::new(positions) });
+        Ok(())
+    }
+
+    /// Decodes the element at `index`. Starts from the closest position of the seek index,
+    /// or from the beginning of the sequence if `build_seek_index` was not called.
+    pub fn get(&self, index: usize) -> Result<BeadReference<'a>, BeadsError> {
+        if index >= self.count {
+            return Err(BeadsError::IndexOutOfBounds { index, len: self.count });
+        }
+        if self.types.len() == 1 && self.types[0].has_no_data() {
+            return Ok(BeadReference { value: 0, buffer: &self.buffer[..0], bead_type: self.types[0] });
+        }
+        self.iter().try_nth(index)
+            .ok_or(BeadsError::IndexOutOfBounds { index, len: self.count })?
+            .map_err(|e| e.shift(self.header_size))
+    }
+
+    /// Counts the elements per type and the bytes spent on tags, values and length prefixes.
+    /// Walks the whole sequence, fails if an element can't be decoded.
+    pub fn stats(&self) -> Result<BeadsStats, BeadsError> {
+        let mut stats = BeadsStats { header_bytes: self.header_size, total_bytes: self.header_size + self.buffer.len(), ..BeadsStats::default() };
+        if self.types.len() == 1 && self.types[0].has_no_data() {
+            stats.count = self.count;
+            stats.type_counts.insert(self.types[0], self.count);
+            return Ok(stats);
+        }
+        let mut iter = self.iter();
+        let mut index = 0;
+        while let Some(result) = iter.try_next() {
+            result.and_then(|bead| stats.add_bead(&bead))
+                .map_err(|e| BeadsError::InvalidElement { index, error: Box::new(e.shift(self.header_size)) })?;
+            index += 1;
+        }
+        if self.types.len() > 1 {
+            let tags_per_byte = match self.types.len() {
+                1..=2 => 8,
+                3..=4 => 4,
+                _ => 2
+            };
+            stats.tag_bytes = self.count.div_ceil(tags_per_byte);
+        }
+        Ok(stats)
+    }
+
+    /// Writes the elements in `range` as a new sequence with the same types and returns the
+    /// number of bytes written. If the range starts at the first element of a tag byte (or the
+    /// sequence has only one type), the encoded elements are copied as they are, otherwise
+    /// every element is tagged again, but the values are still not decoded.
+    pub fn slice<R, W>(&self, range: R, writer: &mut W) -> Result<usize, BeadsError> where R: RangeBounds<usize>, W: io::Write {
+        let (start, end) = bounds_of(range, self.count)?;
+        let count = end - start;
+        let mut tmp = [0; 10];
+        let count_length = add_as_vlq(tmp.as_mut(), count as u128);
+        writer.write_all(&tmp[..count_length])?;
+        let single_type = self.types.len() == 1;
+        if count == 0 || (single_type && self.types[0].has_no_data()) {
+            // same as an encoded builder without any data
+            writer.write_all(&[0])?;
+            return Ok(count_length + 1);
+        }
+        let tags_per_byte = match self.types.len() {
+            1..=2 => 8,
+            3..=4 => 4,
+            _ => 2
+        };
+        if !single_type && start % tags_per_byte != 0 {
+            let mut builder = TypedBeadsBuilder::new(&BeadTypeSet::new(&self.types))?;
+            let mut iter = self.iter();
+            self.skip(&mut iter, start)?;
+            for index in start..end {
+                let bead = iter.try_next()
+                    .ok_or(BeadsError::IndexOutOfBounds { index, len: self.count })?
+                    .map_err(|e| BeadsError::InvalidElement { index, error: Box::new(e.shift(self.header_size)) })?;
+                builder.push_bead(&bead);
+            }
+            let mut data = vec![];
+            builder.encode(&mut data)?;
+            writer.write_all(&data[count_length..])?;
+            return Ok(data.len());
+        }
+
+        let mut iter = self.iter();
+        self.skip(&mut iter, start)?;
+        let (_, tag_cursor, data_cursor) = iter.position();
+        let first = if single_type {
+            data_cursor
+        } else if start == 0 {
+            0
+        } else {
+            max(tag_cursor + 1, data_cursor)
+        };
+        self.skip(&mut iter, count)?;
+        let (_, tag_cursor, data_cursor) = iter.position();
+        if single_type {
+            writer.write_all(&self.buffer[first..data_cursor])?;
+            return Ok(count_length + data_cursor - first);
+        }
+        let last = max(data_cursor, tag_cursor + 1);
+        let mut data = self.buffer[first..last].to_vec();
+        // the last tag byte can carry tags of elements behind the range
+        let used = count % tags_per_byte;
+        if used != 0 {
+            data[tag_cursor - first] &= (1u8 << (used * 8 / tags_per_byte)) - 1;
+        }
+        writer.write_all(&data)?;
+        Ok(count_length + data.len())
+    }
+
+    /// Splits the sequence into two new sequences, the first one holds the elements before `index`.
+    pub fn split_at(&self, index: usize) -> Result<(Vec<u8>, Vec<u8>), BeadsError> {
+        if index > self.count {
+            return Err(BeadsError::IndexOutOfBounds { index, len: self.count });
+        }
+        let mut head = vec![];
+        self.slice(..index, &mut head)?;
+        let mut tail = vec![];
+        self.slice(index.., &mut tail)?;
+        Ok((head, tail))
+    }
+
+    /// Moves `iter` past the next `n` elements.
+    fn skip(&self, iter: &mut BeadsIterator<'a>, n: usize) -> Result<(), BeadsError> {
+        if n == 0 {
+            return Ok(());
+        }
+        if let Some(Err(e)) = iter.try_nth(n - 1) {
+            return Err(BeadsError::InvalidElement { index: BeadsIterator::position(iter).0, error: Box::new(e.shift(self.header_size)) });
+        }
+        Ok(())
+    }
+
+    /// Walks the whole sequence once and checks that every element can be decoded,
+    /// that `Utf8` values are valid UTF-8 and that the sequence covers the buffer exactly.
+    /// The error names the first offending element and a byte offset into the encoded buffer.
+    pub fn validate(&self) -> Result<(), BeadsError> {
+        self.validated_end().map(|_| ())
+    }
+
+    /// Validates the sequence like `validate` and returns the decoder position after the last element.
+    pub(crate) fn validated_end(&self) -> Result<(usize, usize, usize), BeadsError> {
+        if self.types.len() == 1 && self.types[0].has_no_data() {
+            self.check_consumed(1)?;
+            return Ok((self.count, 0, 0));
+        }
+        let mut iter = self.iter();
+        let mut index = 0;
+        while let Some(result) = iter.try_next() {
+            let bead = result.map_err(|e| BeadsError::InvalidElement {
+                index,
+                error: Box::new(e.shift(self.header_size))
+            })?;
+            if bead.is_string() {
+                if let Err(e) = std::str::from_utf8(bead.buffer) {
+                    let offset = self.header_size + iter.consumed() - bead.buffer.len() + e.valid_up_to();
+                    return Err(BeadsError::InvalidElement { index, error: Box::new(BeadsError::InvalidUtf8 { offset }) });
+                }
+            }
+            index += 1;
+        }
+        self.check_consumed(iter.consumed())?;
+        Ok(iter.position())
+    }
+
+    /// The encoded elements, without the count and the types.
+    pub(crate) fn data(&self) -> &'a[u8] {
+        self.buffer
+    }
+
+    /// Size of the count and the types in front of `data`, to turn offsets into `data` into buffer offsets.
+    #[cfg(feature = "serde")]
+    pub(crate) fn header_size(&self) -> usize {
+        self.header_size
+    }
+
+    /// Types of the sequence, in the order of their tags.
+    pub(crate) fn types(&self) -> &[BeadType] {
+        &self.types
+    }
+
+    fn check_consumed(&self, consumed: usize) -> Result<(), BeadsError> {
+        if consumed < self.buffer.len() {
+            return Err(BeadsError::TrailingBytes {
+                offset: self.header_size + consumed,
+                count: self.buffer.len() - consumed
+            });
+        }
+        Ok(())
+    }
+
     pub fn is_symmetrical(&self) -> bool {
         if let Some(t1) = self.types.first() {
             let t1_data_size = t1.data_size();
@@ -65,10 +305,11 @@ impl<'a> TypedBeads<'a> {
         false
     }
 
-    pub fn symmetric(&self) -> Result<SymmetricTypedBeads, &'static str> {
+    pub fn symmetric(&self) -> Result<SymmetricTypedBeads<'a>, BeadsError> {
         if self.is_symmetrical() {
             Ok(SymmetricTypedBeads {
                 buffer: self.buffer,
+                header_size: self.header_size,
                 count: self.count,
                 types: self.types.clone(),
                 data_size: self.types.first().unwrap().data_size() as usize,
@@ -84,13 +325,35 @@ impl<'a> TypedBeads<'a> {
                 }
             })
         } else {
-            Err("Beads sequence is not symmetrical")
+            Err(BeadsError::NotSymmetrical)
         }
     }
 }
 
+/// Start and end of `range` in a sequence of `len` elements.
+fn bounds_of<R>(range: R, len: usize) -> Result<(usize, usize), BeadsError> where R: RangeBounds<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(s) => *s,
+        Bound::Excluded(s) => s.saturating_add(1),
+        Bound::Unbounded => 0
+    };
+    let end = match range.end_bound() {
+        Bound::Included(e) => e.saturating_add(1),
+        Bound::Excluded(e) => *e,
+        Bound::Unbounded => len
+    };
+    if end > len {
+        return Err(BeadsError::IndexOutOfBounds { index: end - 1, len });
+    }
+    if start > end {
+        return Err(BeadsError::IndexOutOfBounds { index: start, len: end });
+    }
+    Ok((start, end))
+}
+
 pub struct SymmetricTypedBeads<'a> {
     buffer: &'a[u8],
+    header_size: usize,
     count: usize,
     types: Vec<BeadType>,
     data_size: usize,
@@ -99,13 +362,16 @@ pub struct SymmetricTypedBeads<'a> {
 }
 
 impl <'a> SymmetricTypedBeads<'a> {
-    pub fn get(&self, index: usize) -> Result<BeadReference<'a>, &'static str> {
+    pub fn get(&self, index: usize) -> Result<BeadReference<'a>, BeadsError> {
         if index >= self.count {
-            return Err("Index is out of bounds");
+            return Err(BeadsError::IndexOutOfBounds { index, len: self.count });
         }
         if self.types.len() == 1 {
             let bead_type = self.types[0];
-            let data_start = index * self.data_size;
+            let data_start = index.saturating_mul(self.data_size);
+            if self.buffer.len() < data_start.saturating_add(self.data_size) {
+                return Err(BeadsError::TruncatedBuffer { offset: self.header_size.saturating_add(data_start), needed: self.data_size });
+            }
             Ok(BeadReference {
                 value: 0,
                 buffer: self.buffer[data_start..(data_start + self.data_size)].as_ref(),
@@ -114,16 +380,24 @@ impl <'a> SymmetricTypedBeads<'a> {
         } else {
             let tag_index = index % self.tags_per_byte;
             let number_of_tag_bytes = index / self.tags_per_byte;
-            let number_of_data_bytes = self.data_size * self.tags_per_byte * number_of_tag_bytes;
-            let tag = self.buffer.get(number_of_tag_bytes + number_of_data_bytes).ok_or("Bad buffer")?;
+            let number_of_data_bytes = (self.data_size * self.tags_per_byte).saturating_mul(number_of_tag_bytes);
+            let tag_offset = number_of_tag_bytes.saturating_add(number_of_data_bytes);
+            let tag = self.buffer.get(tag_offset).ok_or(BeadsError::TruncatedBuffer {
+                offset: self.header_size.saturating_add(tag_offset),
+                needed: 1
+            })?;
             let shift = tag_index * 8 / self.tags_per_byte;
             let mask = self.tag_mask << shift as u8;
             let type_index = ((tag & mask) as usize) >> shift;
-            let bead_type = self.types.get(type_index).ok_or("Bad buffer")?;
+            let bead_type = self.types.get(type_index).ok_or(BeadsError::InvalidTag {
+                offset: self.header_size + tag_offset,
+                tag: type_index,
+                types: self.types.len()
+            })?;
             let tag_addition = if bead_type.has_no_data() { 0 } else { 1 };
-            let data_start = number_of_tag_bytes + tag_addition + index * self.data_size;
-            if self.buffer.len() < data_start + self.data_size {
-                return Err("Bad buffer");
+            let data_start = number_of_tag_bytes + tag_addition + index.saturating_mul(self.data_size);
+            if self.buffer.len() < data_start.saturating_add(self.data_size) {
+                return Err(BeadsError::TruncatedBuffer { offset: self.header_size + data_start, needed: self.data_size });
             }
             Ok(BeadReference {
                 value: 0,
@@ -136,26 +410,37 @@ impl <'a> SymmetricTypedBeads<'a> {
     pub fn len(&self) -> usize {
         self.count
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
+#[derive(Clone, Copy)]
 pub struct IndexedBeads<'a> {
     index_buffer: &'a[u8],
     value_buffer: &'a[u8],
+    value_offset: usize,
+    // number of elements before this view, non zero for results of `slice`
+    first: usize,
     count: usize,
     bytes_per_index_entry: usize
 }
 
 impl<'a> IndexedBeads<'a> {
-    pub fn new(buffer: &'a[u8])-> Result<IndexedBeads<'a>, &'static str> {
+    pub fn new(buffer: &'a[u8])-> Result<IndexedBeads<'a>, BeadsError> {
         let (header_size, header) = read_vlq(buffer)?;
-        let count = (header >> 3) as usize;
+        let count = usize::try_from(header >> 3).unwrap_or(usize::MAX);
         let bytes_per_index = ((header & 7) + 1) as usize;
-        if buffer.len() <= header_size + count * bytes_per_index {
-            return Err("Bad buffer");
+        let index_size = count.saturating_mul(bytes_per_index);
+        if buffer.len() - header_size <= index_size {
+            return Err(BeadsError::TruncatedBuffer { offset: header_size, needed: index_size.saturating_add(1) });
         }
         Ok(IndexedBeads {
-            index_buffer: buffer[header_size..(header_size + count * bytes_per_index)].as_ref(),
-            value_buffer: buffer[(header_size + count * bytes_per_index)..].as_ref(),
+            index_buffer: buffer[header_size..(header_size + index_size)].as_ref(),
+            value_buffer: buffer[(header_size + index_size)..].as_ref(),
+            value_offset: header_size + index_size,
+            first: 0,
             count,
             bytes_per_index_entry: bytes_per_index
         })
@@ -163,34 +448,117 @@ impl<'a> IndexedBeads<'a> {
 
     pub fn len(&self) -> usize { self.count }
 
-    pub fn get(&'a self, index: usize) -> Result<&'a[u8], String> {
+    pub fn is_empty(&self) -> bool { self.count == 0 }
+
+    // `count` empty elements, without an index, as every element starts and ends at 0
+    fn of_empty_values(count: usize) -> IndexedBeads<'a> {
+        IndexedBeads {
+            index_buffer: &[],
+            value_buffer: &[],
+            value_offset: 0,
+            first: 0,
+            count,
+            bytes_per_index_entry: 0
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Result<&'a[u8], BeadsError> {
+        let (start, end) = self.bounds(index)?;
+        Ok(&self.value_buffer[start..end])
+    }
+
+    /// Iterates over the elements, stops at the first element which can't be read.
+    pub fn iter(&self) -> IndexedBeadsIterator<'a> {
+        IndexedBeadsIterator::new(*self)
+    }
+
+    /// The first element, `None` if the sequence is empty or the element can't be read.
+    pub fn first(&self) -> Option<&'a[u8]> {
+        self.iter().next()
+    }
+
+    /// The last element, `None` if the sequence is empty or the element can't be read.
+    pub fn last(&self) -> Option<&'a[u8]> {
+        self.iter().next_back()
+    }
+
+    /// A view on the elements in `range`, without copying. Indices of the view start at 0.
+    pub fn slice<R>(&self, range: R) -> Result<IndexedBeads<'a>, BeadsError> where R: RangeBounds<usize> {
+        let (start, end) = bounds_of(range, self.count)?;
+        Ok(IndexedBeads {
+            first: self.first + start,
+            count: end - start,
+            ..*self
+        })
+    }
+
+    /// The element at `index` as a `TypedBeads` sequence with the given types.
+    pub fn get_typed(&self, index: usize, types: &BeadTypeSet) -> Result<TypedBeads<'a>, BeadsError> {
+        let (start, end) = self.bounds(index)?;
+        TypedBeads::new(&self.value_buffer[start..end], types).map_err(|e| e.shift(self.value_offset + start))
+    }
+
+    /// The element at `index` as a `TypedBeads` sequence which was encoded with its types.
+    pub fn get_typed_types_included(&self, index: usize) -> Result<TypedBeads<'a>, BeadsError> {
+        let (start, end) = self.bounds(index)?;
+        TypedBeads::new_types_included(&self.value_buffer[start..end]).map_err(|e| e.shift(self.value_offset + start))
+    }
+
+    /// The element at `index` as a nested `IndexedBeads` sequence.
+    pub fn get_indexed(&self, index: usize) -> Result<IndexedBeads<'a>, BeadsError> {
+        let (start, end) = self.bounds(index)?;
+        IndexedBeads::new(&self.value_buffer[start..end]).map_err(|e| e.shift(self.value_offset + start))
+    }
+
+    /// The element at `index` as a `FixedSizeBeads` sequence.
+    pub fn get_fixed_size(&self, index: usize) -> Result<FixedSizeBeads<'a>, BeadsError> {
+        let (start, end) = self.bounds(index)?;
+        FixedSizeBeads::new(&self.value_buffer[start..end]).map_err(|e| e.shift(self.value_offset + start))
+    }
+
+    /// Bytes of the header and the index, the values start behind them.
+    pub(crate) fn values_offset(&self) -> usize {
+        self.value_offset
+    }
+
+    /// Start and end of element `index` in the value buffer.
+    pub(crate) fn bounds(&self, index: usize) -> Result<(usize, usize), BeadsError> {
         if index >= self.count {
-            return Err(format!("Bad index: {} where count is: {}", index, self.count))
+            return Err(BeadsError::IndexOutOfBounds { index, len: self.count })
         }
 
-        fn position(b: &[u8], index: usize, bytes_per_index_entry: usize) -> Result<usize, &'static str> {
+        fn position(b: &[u8], index: usize, bytes_per_index_entry: usize) -> Result<usize, BeadsError> {
             let mut position = 0;
             for i in 0..bytes_per_index_entry {
-                let p = b.get(index*bytes_per_index_entry + i).ok_or("Bad index")?;
+                let p = b.get(index*bytes_per_index_entry + i).ok_or(BeadsError::TruncatedBuffer {
+                    offset: index * bytes_per_index_entry,
+                    needed: bytes_per_index_entry
+                })?;
                 let part = *p as usize;
                 position |= part << (i * 8)
             }
             Ok(position)
         }
 
+        let index = self.first + index;
+        let index_offset = self.value_offset - self.index_buffer.len();
         let start = if index == 0 {
             0
         } else {
-            position(self.index_buffer, index - 1, self.bytes_per_index_entry)?
+            position(self.index_buffer, index - 1, self.bytes_per_index_entry).map_err(|e| e.shift(index_offset))?
         };
 
-        let end  = position(self.index_buffer, index, self.bytes_per_index_entry)?;
+        let end  = position(self.index_buffer, index, self.bytes_per_index_entry).map_err(|e| e.shift(index_offset))?;
+
+        if end < start {
+            return Err(BeadsError::InvalidIndexEntry { offset: index_offset + index * self.bytes_per_index_entry })
+        }
 
         if self.value_buffer.len() < end {
-            return Err(format!("Bad buffer offset: {}..{}, where buffer length is: {}", start, end, self.value_buffer.len()))
+            return Err(BeadsError::TruncatedBuffer { offset: self.value_offset + start, needed: end - start })
         }
 
-        Ok(&self.value_buffer[start..end])
+        Ok((start, end))
     }
 }
 
@@ -211,6 +579,7 @@ impl Index<usize> for IndexedBeads<'_> {
             position
         }
 
+        let index = self.first + index;
         let start = if index == 0 {
             0
         } else {
@@ -223,35 +592,129 @@ impl Index<usize> for IndexedBeads<'_> {
     }
 }
 
+impl<'a> IntoIterator for &IndexedBeads<'a> {
+    type Item = &'a[u8];
+    type IntoIter = IndexedBeadsIterator<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[derive(Clone, Copy)]
 pub struct FixedSizeBeads<'a> {
     size: usize,
     buffer: &'a [u8]
 }
 
 impl <'a> FixedSizeBeads<'a> {
-    pub fn new(buffer: &'a[u8]) -> Result<FixedSizeBeads, &'static str> {
+    pub fn new(buffer: &'a[u8]) -> Result<FixedSizeBeads<'a>, BeadsError> {
         let (header_size, header) = read_vlq(buffer)?;
         Ok(FixedSizeBeads {
-            size: header as usize,
+            size: usize::try_from(header).unwrap_or(usize::MAX),
             buffer: &buffer[header_size..]
         })
     }
 
     pub fn len(&self) -> usize {
+        if self.size == 0 {
+            return 0;
+        }
         self.buffer.len() / self.size
     }
 
-    pub fn get(&self, index: usize) -> Result<&'a[u8], &'static str> {
-        let start = index * self.size;
-        let end = (index + 1) * self.size;
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
-        if self.buffer.len() < end {
-            return Err("Bad index")
+    pub fn get(&self, index: usize) -> Result<&'a[u8], BeadsError> {
+        let start = index.saturating_mul(self.size);
+        let end = start.saturating_add(self.size);
+
+        if index >= self.len() || self.buffer.len() < end {
+            return Err(BeadsError::IndexOutOfBounds { index, len: self.len() })
         }
         Ok(&self.buffer[start..end])
     }
+
+    /// Number of bytes per entry.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Entry at `index` as a little endian number, for entries of up to 2 bytes.
+    pub fn get_u16(&self, index: usize) -> Result<u16, BeadsError> {
+        Ok(self.get_uint_of(index, 2, "u16")? as u16)
+    }
+
+    /// Entry at `index` as a little endian number, for entries of up to 4 bytes.
+    pub fn get_u32(&self, index: usize) -> Result<u32, BeadsError> {
+        Ok(self.get_uint_of(index, 4, "u32")? as u32)
+    }
+
+    /// Entry at `index` as a little endian number, for entries of up to 8 bytes.
+    pub fn get_u64(&self, index: usize) -> Result<u64, BeadsError> {
+        Ok(self.get_uint_of(index, 8, "u64")? as u64)
+    }
+
+    /// Entry at `index` as a little endian number, for entries of up to 16 bytes.
+    pub fn get_u128(&self, index: usize) -> Result<u128, BeadsError> {
+        self.get_uint_of(index, 16, "u128")
+    }
+
+    fn get_uint_of(&self, index: usize, max_size: usize, target: &'static str) -> Result<u128, BeadsError> {
+        let entry = self.get(index)?;
+        if entry.len() > max_size {
+            return Err(BeadsError::OutOfRange { value: format!("{:?}", entry), target });
+        }
+        Ok(u128_from_slice(entry))
+    }
+
+    /// Iterates over the entries as little endian numbers. Fails if the entries are wider than 16 bytes.
+    pub fn iter_uint(&self) -> Result<impl DoubleEndedIterator<Item = u128> + ExactSizeIterator + 'a, BeadsError> {
+        if self.size > 16 {
+            return Err(BeadsError::OutOfRange { value: format!("entries of {} bytes", self.size), target: "u128" });
+        }
+        // entries of size 0 hold no elements, chunks of size 0 are not allowed though
+        let entries = if self.size == 0 { &self.buffer[..0] } else { self.buffer };
+        Ok(entries.chunks_exact(max(self.size, 1)).map(u128_from_slice))
+    }
+
+    /// The entries as a slice of `T` without copying. Returns `None` if the entries are not
+    /// exactly `size_of::<T>()` bytes wide, the host is not little endian or the entries
+    /// are not aligned for `T` in memory, `iter_uint` works in all of those cases.
+    pub fn as_slice<T>(&self) -> Option<&'a [T]> where T: FixedSizeUint {
+        if cfg!(target_endian = "little") == false || self.size != mem::size_of::<T>() {
+            return None;
+        }
+        let entries = &self.buffer[..self.len() * self.size];
+        if entries.as_ptr().align_offset(mem::align_of::<T>()) != 0 {
+            return None;
+        }
+        // SAFETY: the pointer is aligned for `T`, covers `len()` entries of `size_of::<T>()` bytes,
+        // every bit pattern is a valid unsigned integer and the byte order matches the host
+        Some(unsafe { std::slice::from_raw_parts(entries.as_ptr() as *const T, self.len()) })
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Unsigned integers `FixedSizeBeads::as_slice` can hand out.
+pub trait FixedSizeUint: sealed::Sealed + Copy {}
+
+macro_rules! fixed_size_uint {
+    ( $( $x:ident ),* ) => {
+        $(
+impl sealed::Sealed for $x {}
+impl FixedSizeUint for $x {}
+        )*
+    }
 }
 
+fixed_size_uint![u8, u16, u32, u64, u128];
+
 impl Index<usize> for FixedSizeBeads<'_> {
     type Output = [u8];
 
@@ -263,28 +726,379 @@ impl Index<usize> for FixedSizeBeads<'_> {
     }
 }
 
+/// Sequence of values where every unique value is stored once. Reads the index and the
+/// dictionary of unique values on construction, the accessors only borrow from the buffer.
+#[derive(Clone, Copy)]
 pub struct DedupBeads<'a> {
-    buffer: &'a[u8]
+    codes: DedupCodeBeads<'a>,
+    dictionary: IndexedBeads<'a>,
+}
+
+// the codes of a `DedupBeads` sequence, written by `beads_to_dedup_beads_with_codes`
+#[derive(Clone, Copy)]
+enum DedupCodeBeads<'a> {
+    FixedSize(FixedSizeBeads<'a>),
+    BitPacked(BitPackedBeads<'a>),
 }
 
 impl <'a> DedupBeads<'a> {
-    pub fn new(buffer: &'a[u8]) -> DedupBeads<'a> {
-        DedupBeads {
-            buffer
+    /// Reads the codes as fixed size entries, or bit-packed if the sequence has a third element.
+    /// Bit-packed codes are stored behind the dictionary and the first element is empty,
+    /// so readers which know only fixed size codes fail instead of reading wrong values.
+    pub fn new(buffer: &'a[u8]) -> Result<DedupBeads<'a>, BeadsError> {
+        let root = IndexedBeads::new(buffer)?;
+        let codes = if root.len() > 2 {
+            let (start, end) = root.bounds(0)?;
+            if start != end {
+                let error = BeadsError::TrailingBytes { offset: root.values_offset() + start, count: end - start };
+                return Err(BeadsError::InvalidElement { index: 0, error: Box::new(error) });
+            }
+            let (start, _) = root.bounds(2)?;
+            DedupCodeBeads::BitPacked(BitPackedBeads::new(root.get(2)?).map_err(|e| e.shift(root.values_offset() + start))?)
+        } else {
+            let codes = root.get_fixed_size(0)?;
+            if codes.size() > 16 {
+                return Err(BeadsError::OutOfRange { value: format!("codes of {} bytes", codes.size()), target: "u128" });
+            }
+            DedupCodeBeads::FixedSize(codes)
+        };
+        let mut dedup = DedupBeads { codes, dictionary: IndexedBeads::of_empty_values(0) };
+        // a dictionary without bytes is written if all values have an empty payload,
+        // it's empty for an empty sequence and has the single empty value otherwise
+        if !root.get(1)?.is_empty() {
+            dedup.dictionary = root.get_indexed(1)?;
+        } else if !dedup.is_empty() {
+            dedup.dictionary = IndexedBeads::of_empty_values(1);
+        }
+        Ok(dedup)
+    }
+
+    pub fn len(&self) -> usize {
+        match self.codes {
+            DedupCodeBeads::FixedSize(codes) => codes.len(),
+            DedupCodeBeads::BitPacked(codes) => codes.len()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Result<&'a[u8], BeadsError> {
+        self.dictionary.get(self.code(index)?)
+    }
+
+    /// Index of the value at `index` in the `dictionary`.
+    pub fn code(&self, index: usize) -> Result<usize, BeadsError> {
+        let code = match self.codes {
+            DedupCodeBeads::FixedSize(codes) => codes.get_u128(index)?,
+            DedupCodeBeads::BitPacked(codes) => codes.get(index)?
+        };
+        Ok(usize::try_from(code).unwrap_or(usize::MAX))
+    }
+
+    /// The unique values, in the order they first appeared.
+    pub fn dictionary(&self) -> IndexedBeads<'a> {
+        self.dictionary
+    }
+
+    /// Iterates over the values, stops at the first value which can't be read.
+    pub fn iter(&self) -> DedupBeadsIterator<'a> {
+        DedupBeadsIterator::new(*self)
+    }
+
+    /// The value at `index` decoded as a bead. The dictionary stores only the payload of a bead,
+    /// so the type is the one in `types` which can have a payload like it. Fails with `AmbiguousTypes`
+    /// if two of the `types` can have payloads of the same size, like `U8` and `I8` or `None` and `Utf8`.
+    pub fn get_bead(&self, index: usize, types: &BeadTypeSet) -> Result<BeadReference<'a>, BeadsError> {
+        Self::to_bead(self.get(index)?, &Self::payload_types(types)?)
+    }
+
+    /// All unique values decoded as beads, see `get_bead`. Can be indexed with `code`.
+    pub fn dictionary_beads(&self, types: &BeadTypeSet) -> Result<Vec<BeadReference<'a>>, BeadsError> {
+        let types = Self::payload_types(types)?;
+        let mut beads = Vec::with_capacity(self.dictionary.len());
+        for index in 0..self.dictionary.len() {
+            beads.push(Self::to_bead(self.dictionary.get(index)?, &types)?);
+        }
+        Ok(beads)
+    }
+
+    // the types of the set, if no two of them can have payloads of the same size
+    fn payload_types(types: &BeadTypeSet) -> Result<Vec<BeadType>, BeadsError> {
+        // smallest and largest payload size of a value of `bead_type`
+        fn payload_sizes(bead_type: BeadType) -> (usize, usize) {
+            match bead_type {
+                BeadType::Utf8 | BeadType::Bytes => (0, usize::MAX),
+                // a vlq of up to 128 bits
+                BeadType::Vlq | BeadType::VlqZ => (1, 19),
+                _ => (bead_type.data_size() as usize, bead_type.data_size() as usize)
+            }
+        }
+
+        let types = types.types_by_priority()?;
+        for (i, first) in types.iter().copied().enumerate() {
+            let (first_min, first_max) = payload_sizes(first);
+            for second in types[i + 1..].iter().copied() {
+                let (second_min, second_max) = payload_sizes(second);
+                if first_min <= second_max && second_min <= first_max {
+                    return Err(BeadsError::AmbiguousTypes { first, second })
+                }
+            }
+        }
+        Ok(types)
+    }
+
+    fn to_bead(entry: &'a[u8], types: &[BeadType]) -> Result<BeadReference<'a>, BeadsError> {
+        for bead_type in types.iter().copied() {
+            let value = match bead_type {
+                BeadType::Utf8 | BeadType::Bytes => entry.len() as u128,
+                BeadType::Vlq | BeadType::VlqZ => match read_vlq(entry) {
+                    Ok((length, value)) if length == entry.len() => value,
+                    _ => continue
+                },
+                _ => if bead_type.data_size() as usize == entry.len() { 0 } else { continue }
+            };
+            return Ok(BeadReference { value, buffer: entry, bead_type });
+        }
+        Err(BeadsError::UnparsableValue(format!("{:?}", entry)))
+    }
+}
+
+/// Sequence written by `DeltaBeadsBuilder`. The stored elements are differences,
+/// iterating reconstructs the pushed values.
+pub struct DeltaBeads<'a> {
+    beads: TypedBeads<'a>,
+    delta_of_delta: bool,
+}
+
+impl<'a> DeltaBeads<'a> {
+    pub fn new(buffer: &'a[u8], types: &BeadTypeSet) -> Result<DeltaBeads<'a>, BeadsError> {
+        let delta_of_delta = Self::read_order(buffer)?;
+        let mut beads = TypedBeads::new(&buffer[1..], types).map_err(|e| e.shift(1))?;
+        beads.header_size += 1;
+        Ok(DeltaBeads { beads, delta_of_delta })
+    }
+
+    /// Same as `new`, for a buffer written by `DeltaBeadsBuilder::encode_with_types`.
+    pub fn new_types_included(buffer: &'a[u8]) -> Result<DeltaBeads<'a>, BeadsError> {
+        let delta_of_delta = Self::read_order(buffer)?;
+        let mut beads = TypedBeads::new_types_included(&buffer[1..]).map_err(|e| e.shift(1))?;
+        beads.header_size += 1;
+        Ok(DeltaBeads { beads, delta_of_delta })
+    }
+
+    pub fn len(&self) -> usize {
+        self.beads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_delta_of_delta(&self) -> bool {
+        self.delta_of_delta
+    }
+
+    /// The unit of the values, if they were pushed as timestamps, see `BeadTypeSet::with_time`.
+    pub fn time(&self) -> Option<TimeKind> {
+        self.beads.time()
+    }
+
+    pub fn iter(&self) -> DeltaBeadsIterator<'a> {
+        DeltaBeadsIterator::new(self.beads.iter(), self.delta_of_delta)
+    }
+
+    /// All values, or the first element which could not be decoded as `InvalidElement`.
+    pub fn to_vec(&self) -> Result<Vec<i128>, BeadsError> {
+        let mut iter = self.iter();
+        let mut values = vec![];
+        while let Some(result) = iter.try_next() {
+            values.push(result.map_err(|e| BeadsError::InvalidElement {
+                index: values.len(),
+                error: Box::new(e.shift(self.beads.header_size))
+            })?);
+        }
+        Ok(values)
+    }
+
+    fn read_order(buffer: &[u8]) -> Result<bool, BeadsError> {
+        match buffer.first() {
+            Some(1) => Ok(false),
+            Some(2) => Ok(true),
+            Some(order) => Err(BeadsError::OutOfRange { value: order.to_string(), target: "delta order" }),
+            None => Err(BeadsError::TruncatedBuffer { offset: 0, needed: 1 })
+        }
+    }
+}
+
+// distance between the checkpoints of the run values, so `RleBeads::get` decodes at most this many values
+const RLE_SEEK_STEP: usize = 16;
+
+/// Sequence written by `RleBeadsBuilder`. Reads the run lengths on construction,
+/// so `len` does not expand the runs and `get` finds the run of an element with a binary search.
+pub struct RleBeads<'a> {
+    values: TypedBeads<'a>,
+    lengths: TypedBeads<'a>,
+    // index of the element after each run
+    ends: Vec<usize>,
+}
+
+impl<'a> RleBeads<'a> {
+    pub fn new(buffer: &'a[u8], types: &BeadTypeSet) -> Result<RleBeads<'a>, BeadsError> {
+        let indexed = IndexedBeads::new(buffer)?;
+        Self::from_indexed(&indexed, indexed.get_typed(0, types)?)
+    }
+
+    /// Same as `new`, for a buffer written by `RleBeadsBuilder::encode_with_types`.
+    pub fn new_types_included(buffer: &'a[u8]) -> Result<RleBeads<'a>, BeadsError> {
+        let indexed = IndexedBeads::new(buffer)?;
+        Self::from_indexed(&indexed, indexed.get_typed_types_included(0)?)
+    }
+
+    fn from_indexed(indexed: &IndexedBeads<'a>, mut values: TypedBeads<'a>) -> Result<RleBeads<'a>, BeadsError> {
+        let lengths = indexed.get_typed(1, &BeadTypeSet::new(&[BeadType::Vlq]))?;
+        if values.len() != lengths.len() {
+            return Err(BeadsError::ColumnLengthMismatch { column: 1, len: lengths.len(), rows: values.len() });
+        }
+        let offset = |index| indexed.values_offset() + indexed.bounds(index).map_or(0, |(start, _)| start);
+        // after validation every run length takes at least one byte, so there are not more than the buffer size
+        lengths.validate().map_err(|e| e.shift(offset(1)))?;
+        values.validate().map_err(|e| e.shift(offset(0)))?;
+        let mut ends = Vec::with_capacity(lengths.len());
+        let mut end = 0usize;
+        for (index, run) in lengths.iter().enumerate() {
+            let run = usize::try_from(run.to_uint()).ok()
+                .filter(|run| *run > 0)
+                .and_then(|run| end.checked_add(run))
+                .ok_or_else(|| BeadsError::InvalidElement {
+                    index,
+                    error: Box::new(BeadsError::OutOfRange { value: run.to_uint().to_string(), target: "run length" })
+                })?;
+            end = run;
+            ends.push(end);
+        }
+        values.build_seek_index(RLE_SEEK_STEP)?;
+        Ok(RleBeads { values, lengths, ends })
+    }
+
+    /// Number of elements, with every run expanded.
+    pub fn len(&self) -> usize {
+        self.ends.last().copied().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of runs, which is the number of stored values.
+    pub fn runs(&self) -> usize {
+        self.ends.len()
+    }
+
+    pub fn get(&self, index: usize) -> Result<BeadReference<'a>, BeadsError> {
+        if index >= self.len() {
+            return Err(BeadsError::IndexOutOfBounds { index, len: self.len() });
+        }
+        self.values.get(self.ends.partition_point(|end| *end <= index))
+    }
+
+    pub fn iter(&self) -> RleBeadsIterator<'a> {
+        RleBeadsIterator::new(self.values.iter(), self.lengths.iter(), self.len())
+    }
+}
+
+/// Sequence written by `BitPackedBeadsBuilder`, every value is read directly from its bits.
+#[derive(Clone, Copy)]
+pub struct BitPackedBeads<'a> {
+    buffer: &'a[u8],
+    count: usize,
+    reference: u128,
+    bits: usize,
+}
+
+impl<'a> BitPackedBeads<'a> {
+    pub fn new(buffer: &'a[u8]) -> Result<BitPackedBeads<'a>, BeadsError> {
+        let (count_length, count) = read_vlq(buffer)?;
+        let (reference_length, reference) = read_vlq(&buffer[count_length..]).map_err(|e| e.shift(count_length))?;
+        let header_size = count_length + reference_length + 1;
+        if buffer.len() < header_size {
+            return Err(BeadsError::TruncatedBuffer { offset: header_size - 1, needed: 1 });
+        }
+        let bits = buffer[header_size - 1] as usize;
+        if bits > 128 {
+            return Err(BeadsError::OutOfRange { value: format!("{} bits", bits), target: "u128" });
+        }
+        let count = usize::try_from(count).unwrap_or(usize::MAX);
+        let needed = count.checked_mul(bits).map(|bits| bits.div_ceil(8));
+        let data = &buffer[header_size..];
+        match needed {
+            Some(needed) if needed == data.len() => Ok(BitPackedBeads { buffer: data, count, reference, bits }),
+            Some(needed) if needed < data.len() => Err(BeadsError::TrailingBytes { offset: header_size + needed, count: data.len() - needed }),
+            _ => Err(BeadsError::TruncatedBuffer { offset: header_size, needed: needed.unwrap_or(usize::MAX) })
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of bits per value.
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// The smallest value, all values are stored as the difference to it.
+    pub fn reference(&self) -> u128 {
+        self.reference
+    }
+
+    pub fn get(&self, index: usize) -> Result<u128, BeadsError> {
+        if index >= self.count {
+            return Err(BeadsError::IndexOutOfBounds { index, len: self.count });
+        }
+        self.with_reference(self.delta(index))
+    }
+
+    pub fn iter(&self) -> BitPackedBeadsIterator<'a> {
+        BitPackedBeadsIterator::new(*self)
+    }
+
+    /// The bits of value `index`, which has to be smaller than `count`.
+    pub(crate) fn delta(&self, index: usize) -> u128 {
+        if self.bits == 0 {
+            return 0;
+        }
+        let start = index * self.bits;
+        let end = (start + self.bits).div_ceil(8);
+        let offset = start % 8;
+        let mut delta = 0u128;
+        for (i, byte) in self.buffer[start / 8..end].iter().enumerate() {
+            // a value spans up to 17 bytes, the bits of the first byte below `offset` belong to the previous value
+            let position = i * 8;
+            if position < offset {
+                delta |= (*byte >> offset) as u128;
+            } else if position - offset < 128 {
+                delta |= (*byte as u128) << (position - offset);
+            }
         }
+        self.mask(delta)
+    }
+
+    pub(crate) fn mask(&self, delta: u128) -> u128 {
+        if self.bits == 128 { delta } else { delta & ((1 << self.bits) - 1) }
     }
 
-    pub fn len(&self) -> Result<usize, String> {
-        let root = IndexedBeads::new(self.buffer)?;
-        let index_beads = FixedSizeBeads::new(root.get(0)?)?;
-        Ok(index_beads.len())
+    pub(crate) fn data(&self) -> &'a[u8] {
+        self.buffer
     }
 
-    pub fn get(&self, index: usize) -> Result<Vec<u8>, String> {
-        let root = IndexedBeads::new(self.buffer)?;
-        let index_beads = FixedSizeBeads::new(root.get(0)?)?;
-        let values = IndexedBeads::new(root.get(1)?)?;
-        let index = u128_from_slice(&index_beads.get(index)?) as usize;
-        Ok(values.get(index)?.to_vec())
+    pub(crate) fn with_reference(&self, delta: u128) -> Result<u128, BeadsError> {
+        self.reference.checked_add(delta)
+            .ok_or_else(|| BeadsError::OutOfRange { value: format!("{} + {}", self.reference, delta), target: "u128" })
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,614 @@
+use std::convert::TryFrom;
+use serde::{ser, de, Serialize};
+use serde::de::IntoDeserializer;
+use crate::builder::TypedBeadsBuilder;
+use crate::error::BeadsError;
+use crate::iterator::BeadsIterator;
+use crate::reference::BeadReference;
+use crate::sequence::TypedBeads;
+
+// Values are flattened into one Beads sequence. Structs and tuples write their fields one after
+// another, sequences and maps write their length first and enums write the variant index first.
+// `None` and `()` both become a `None` bead, so `Option<()>` can't tell `Some(())` from `None`.
+
+/// Pushes every value into the builder. If a value can't be represented with the builder's type set,
+/// the error is returned and the builder may hold the beads of a partially written value.
+pub fn to_builder<T>(values: &[T], builder: &mut TypedBeadsBuilder) -> Result<(), BeadsError> where T: Serialize {
+    let mut serializer = Serializer { builder };
+    for v in values {
+        v.serialize(&mut serializer)?;
+    }
+    Ok(())
+}
+
+/// Reads values until the sequence is exhausted. An element which can't be decoded is reported
+/// as `InvalidElement`, so a damaged buffer doesn't come back as a shorter list of values.
+pub fn from_beads<'a, T>(beads: &'a TypedBeads) -> Result<Vec<T>, BeadsError> where T: de::Deserialize<'a> {
+    let mut deserializer = Deserializer::new(beads);
+    let mut result = vec![];
+    while deserializer.peek_bead()?.is_some() {
+        result.push(T::deserialize(&mut deserializer)?);
+    }
+    Ok(result)
+}
+
+impl ser::Error for BeadsError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        BeadsError::Custom(msg.to_string())
+    }
+}
+
+impl de::Error for BeadsError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        BeadsError::Custom(msg.to_string())
+    }
+}
+
+pub struct Serializer<'b> {
+    builder: &'b mut TypedBeadsBuilder
+}
+
+impl<'b> Serializer<'b> {
+    pub fn new(builder: &'b mut TypedBeadsBuilder) -> Serializer<'b> {
+        Serializer {
+            builder
+        }
+    }
+
+    fn pushed(added: bool, value: &dyn std::fmt::Display) -> Result<(), BeadsError> {
+        if added {
+            Ok(())
+        } else {
+            Err(BeadsError::UnsupportedValue(value.to_string()))
+        }
+    }
+
+    fn push_len(&mut self, len: Option<usize>) -> Result<(), BeadsError> {
+        let len = len.ok_or_else(|| BeadsError::Custom("sequence length must be known up front".to_string()))?;
+        Self::pushed(self.builder.push_uint(len as u128), &len)
+    }
+}
+
+impl<'a, 'b> ser::Serializer for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = BeadsError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<(), BeadsError> {
+        Serializer::pushed(self.builder.push_bool(v), &v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), BeadsError> { self.serialize_i128(v as i128) }
+    fn serialize_i16(self, v: i16) -> Result<(), BeadsError> { self.serialize_i128(v as i128) }
+    fn serialize_i32(self, v: i32) -> Result<(), BeadsError> { self.serialize_i128(v as i128) }
+    fn serialize_i64(self, v: i64) -> Result<(), BeadsError> { self.serialize_i128(v as i128) }
+
+    fn serialize_i128(self, v: i128) -> Result<(), BeadsError> {
+        Serializer::pushed(self.builder.push_int(v), &v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), BeadsError> { self.serialize_u128(v as u128) }
+    fn serialize_u16(self, v: u16) -> Result<(), BeadsError> { self.serialize_u128(v as u128) }
+    fn serialize_u32(self, v: u32) -> Result<(), BeadsError> { self.serialize_u128(v as u128) }
+    fn serialize_u64(self, v: u64) -> Result<(), BeadsError> { self.serialize_u128(v as u128) }
+
+    fn serialize_u128(self, v: u128) -> Result<(), BeadsError> {
+        Serializer::pushed(self.builder.push_uint(v), &v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), BeadsError> { self.serialize_f64(v as f64) }
+
+    fn serialize_f64(self, v: f64) -> Result<(), BeadsError> {
+        Serializer::pushed(self.builder.push_double(v), &v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), BeadsError> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), BeadsError> {
+        Serializer::pushed(self.builder.push_string(v), &v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), BeadsError> {
+        Serializer::pushed(self.builder.push_bytes(v), &format!("{:?}", v))
+    }
+
+    fn serialize_none(self) -> Result<(), BeadsError> {
+        Serializer::pushed(self.builder.push_none(), &"None")
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), BeadsError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), BeadsError> {
+        self.serialize_none()
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), BeadsError> {
+        self.serialize_none()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str) -> Result<(), BeadsError> {
+        self.serialize_u32(variant_index)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<(), BeadsError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, variant_index: u32, _variant: &'static str, value: &T) -> Result<(), BeadsError> {
+        self.serialize_u32(variant_index)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self, BeadsError> {
+        self.push_len(len)?;
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, BeadsError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self, BeadsError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self, BeadsError> {
+        self.serialize_u32(variant_index)?;
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self, BeadsError> {
+        self.push_len(len)?;
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, BeadsError> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self, BeadsError> {
+        self.serialize_u32(variant_index)?;
+        Ok(self)
+    }
+}
+
+macro_rules! serialize_elements {
+    ( $( $t:ident, $m:ident );* ) => {
+        $(
+impl<'a, 'b> ser::$t for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = BeadsError;
+
+    fn $m<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), BeadsError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), BeadsError> {
+        Ok(())
+    }
+}
+        )*
+    }
+}
+
+serialize_elements![
+    SerializeSeq, serialize_element;
+    SerializeTuple, serialize_element;
+    SerializeTupleStruct, serialize_field;
+    SerializeTupleVariant, serialize_field
+];
+
+impl<'a, 'b> ser::SerializeMap for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = BeadsError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), BeadsError> {
+        key.serialize(&mut **self)
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), BeadsError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), BeadsError> {
+        Ok(())
+    }
+}
+
+impl<'a, 'b> ser::SerializeStruct for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = BeadsError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _key: &'static str, value: &T) -> Result<(), BeadsError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), BeadsError> {
+        Ok(())
+    }
+}
+
+impl<'a, 'b> ser::SerializeStructVariant for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = BeadsError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _key: &'static str, value: &T) -> Result<(), BeadsError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), BeadsError> {
+        Ok(())
+    }
+}
+
+pub struct Deserializer<'a> {
+    beads: BeadsIterator<'a>,
+    peeked: Option<BeadReference<'a>>,
+    header_size: usize,
+    index: usize,
+    len: usize
+}
+
+impl<'a> Deserializer<'a> {
+    pub fn new(beads: &'a TypedBeads) -> Deserializer<'a> {
+        Deserializer {
+            beads: beads.iter(),
+            peeked: None,
+            header_size: beads.header_size(),
+            index: 0,
+            len: beads.len()
+        }
+    }
+
+    fn peek_bead(&mut self) -> Result<Option<BeadReference<'a>>, BeadsError> {
+        if self.peeked.is_none() {
+            if let Some(result) = self.beads.try_next() {
+                let bead = result.map_err(|e| BeadsError::InvalidElement { index: self.index, error: Box::new(e.shift(self.header_size)) })?;
+                self.peeked = Some(bead);
+            }
+        }
+        Ok(self.peeked)
+    }
+
+    fn next_bead(&mut self) -> Result<BeadReference<'a>, BeadsError> {
+        let bead = self.peek_bead()?.ok_or(BeadsError::IndexOutOfBounds { index: self.index, len: self.len })?;
+        self.peeked = None;
+        self.index += 1;
+        Ok(bead)
+    }
+
+    fn next_len(&mut self) -> Result<usize, BeadsError> {
+        usize::try_from(self.next_bead()?)
+    }
+}
+
+macro_rules! deserialize_number {
+    ( $( $m:ident, $t:ident, $v:ident );* ) => {
+        $(
+    fn $m<V: de::Visitor<'a>>(self, visitor: V) -> Result<V::Value, BeadsError> {
+        visitor.$v($t::try_from(self.next_bead()?)?)
+    }
+        )*
+    }
+}
+
+impl<'a> de::Deserializer<'a> for &mut Deserializer<'a> {
+    type Error = BeadsError;
+
+    fn deserialize_any<V: de::Visitor<'a>>(self, visitor: V) -> Result<V::Value, BeadsError> {
+        let bead = self.next_bead()?;
+        if bead.is_none() {
+            visitor.visit_unit()
+        } else if bead.is_bool() {
+            visitor.visit_bool(bead.try_to_bool()?)
+        } else if bead.is_uint() {
+            visitor.visit_u128(bead.try_to_uint()?)
+        } else if bead.is_int() {
+            visitor.visit_i128(bead.try_to_int()?)
+        } else if bead.is_float() {
+            visitor.visit_f64(bead.try_to_float()?)
+        } else if bead.is_string() {
+            visitor.visit_borrowed_str(bead.try_to_str()?)
+        } else {
+            visitor.visit_borrowed_bytes(bead.to_bytes())
+        }
+    }
+
+    fn deserialize_bool<V: de::Visitor<'a>>(self, visitor: V) -> Result<V::Value, BeadsError> {
+        visitor.visit_bool(self.next_bead()?.try_to_bool()?)
+    }
+
+    deserialize_number![
+        deserialize_i8, i8, visit_i8;
+        deserialize_i16, i16, visit_i16;
+        deserialize_i32, i32, visit_i32;
+        deserialize_i64, i64, visit_i64;
+        deserialize_i128, i128, visit_i128;
+        deserialize_u8, u8, visit_u8;
+        deserialize_u16, u16, visit_u16;
+        deserialize_u32, u32, visit_u32;
+        deserialize_u64, u64, visit_u64;
+        deserialize_u128, u128, visit_u128;
+        deserialize_f32, f32, visit_f32;
+        deserialize_f64, f64, visit_f64
+    ];
+
+    fn deserialize_char<V: de::Visitor<'a>>(self, visitor: V) -> Result<V::Value, BeadsError> {
+        let s = self.next_bead()?.try_to_str()?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(BeadsError::OutOfRange { value: s.to_string(), target: "char" })
+        }
+    }
+
+    fn deserialize_str<V: de::Visitor<'a>>(self, visitor: V) -> Result<V::Value, BeadsError> {
+        visitor.visit_borrowed_str(self.next_bead()?.try_to_str()?)
+    }
+
+    fn deserialize_string<V: de::Visitor<'a>>(self, visitor: V) -> Result<V::Value, BeadsError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'a>>(self, visitor: V) -> Result<V::Value, BeadsError> {
+        let bead = self.next_bead()?;
+        if bead.is_bytes() || bead.is_string() {
+            visitor.visit_borrowed_bytes(bead.to_bytes())
+        } else {
+            Err(BeadsError::TypeMismatch { found: bead.bead_type, expected: "bytes" })
+        }
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'a>>(self, visitor: V) -> Result<V::Value, BeadsError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: de::Visitor<'a>>(self, visitor: V) -> Result<V::Value, BeadsError> {
+        if self.peek_bead()?.map(|b| b.is_none()).unwrap_or(false) {
+            self.next_bead()?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: de::Visitor<'a>>(self, visitor: V) -> Result<V::Value, BeadsError> {
+        let bead = self.next_bead()?;
+        if bead.is_none() {
+            visitor.visit_unit()
+        } else {
+            Err(BeadsError::TypeMismatch { found: bead.bead_type, expected: "unit" })
+        }
+    }
+
+    fn deserialize_unit_struct<V: de::Visitor<'a>>(self, _name: &'static str, visitor: V) -> Result<V::Value, BeadsError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'a>>(self, _name: &'static str, visitor: V) -> Result<V::Value, BeadsError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: de::Visitor<'a>>(self, visitor: V) -> Result<V::Value, BeadsError> {
+        let len = self.next_len()?;
+        visitor.visit_seq(Elements { de: self, remaining: len })
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'a>>(self, len: usize, visitor: V) -> Result<V::Value, BeadsError> {
+        visitor.visit_seq(Elements { de: self, remaining: len })
+    }
+
+    fn deserialize_tuple_struct<V: de::Visitor<'a>>(self, _name: &'static str, len: usize, visitor: V) -> Result<V::Value, BeadsError> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: de::Visitor<'a>>(self, visitor: V) -> Result<V::Value, BeadsError> {
+        let len = self.next_len()?;
+        visitor.visit_map(Elements { de: self, remaining: len })
+    }
+
+    fn deserialize_struct<V: de::Visitor<'a>>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value, BeadsError> {
+        self.deserialize_tuple(fields.len(), visitor)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'a>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, BeadsError> {
+        visitor.visit_enum(self)
+    }
+
+    fn deserialize_identifier<V: de::Visitor<'a>>(self, visitor: V) -> Result<V::Value, BeadsError> {
+        self.deserialize_u32(visitor)
+    }
+
+    fn deserialize_ignored_any<V: de::Visitor<'a>>(self, visitor: V) -> Result<V::Value, BeadsError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+struct Elements<'b, 'a> {
+    de: &'b mut Deserializer<'a>,
+    remaining: usize
+}
+
+impl<'a, 'b> de::SeqAccess<'a> for Elements<'b, 'a> {
+    type Error = BeadsError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'a>>(&mut self, seed: T) -> Result<Option<T::Value>, BeadsError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'a, 'b> de::MapAccess<'a> for Elements<'b, 'a> {
+    type Error = BeadsError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'a>>(&mut self, seed: K) -> Result<Option<K::Value>, BeadsError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'a>>(&mut self, seed: V) -> Result<V::Value, BeadsError> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'a> de::EnumAccess<'a> for &mut Deserializer<'a> {
+    type Error = BeadsError;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'a>>(self, seed: V) -> Result<(V::Value, Self), BeadsError> {
+        let variant_index = u32::try_from(self.next_bead()?)?;
+        let value = seed.deserialize(IntoDeserializer::<BeadsError>::into_deserializer(variant_index))?;
+        Ok((value, self))
+    }
+}
+
+impl<'a> de::VariantAccess<'a> for &mut Deserializer<'a> {
+    type Error = BeadsError;
+
+    fn unit_variant(self) -> Result<(), BeadsError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'a>>(self, seed: T) -> Result<T::Value, BeadsError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: de::Visitor<'a>>(self, len: usize, visitor: V) -> Result<V::Value, BeadsError> {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V: de::Visitor<'a>>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value, BeadsError> {
+        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Serialize, Deserialize};
+    use std::collections::BTreeMap;
+    use crate::bead_type::{BeadTypeSet, BeadType};
+    use crate::builder::TypedBeadsBuilder;
+    use crate::sequence::TypedBeads;
+    use crate::serde_beads::{to_builder, from_beads};
+    use crate::error::BeadsError;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Kind {
+        Plain,
+        Tagged(String),
+        Point { x: i32, y: i32 },
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Person {
+        name: String,
+        age: u8,
+        height: Option<f32>,
+        active: bool,
+        scores: Vec<i64>,
+        kind: Kind,
+    }
+
+    #[test]
+    fn roundtrip_structs() {
+        let types = BeadTypeSet::new(&[BeadType::None, BeadType::TrueFlag, BeadType::FalseFlag, BeadType::U8, BeadType::I8, BeadType::VlqZ, BeadType::F32, BeadType::Utf8]);
+        let people = vec![
+            Person { name: "Maxim".to_string(), age: 40, height: Some(1.5), active: true, scores: vec![1, -2, 300_000], kind: Kind::Plain },
+            Person { name: "Alex".to_string(), age: 7, height: None, active: false, scores: vec![], kind: Kind::Tagged("x".to_string()) },
+            Person { name: "🤪".to_string(), age: 255, height: Some(0.25), active: true, scores: vec![5], kind: Kind::Point { x: -1, y: 2 } },
+        ];
+        let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+        to_builder(&people, &mut builder).unwrap();
+        let mut buffer = vec![];
+        builder.encode(&mut buffer).unwrap();
+
+        let beads = TypedBeads::new(&buffer, &types).ok().unwrap();
+        assert!(beads.validate().is_ok());
+        let back: Vec<Person> = from_beads(&beads).unwrap();
+        assert_eq!(back, people);
+    }
+
+    #[test]
+    fn roundtrip_primitives_borrowed_strings_and_maps() {
+        let types = BeadTypeSet::new(&[BeadType::None, BeadType::U8, BeadType::Utf8, BeadType::Bytes]);
+        let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+        to_builder(&[Some("a"), None, Some("b")], &mut builder).unwrap();
+        let mut map = BTreeMap::new();
+        map.insert("one".to_string(), 1u8);
+        map.insert("two".to_string(), 2u8);
+        to_builder(&[map.clone()], &mut builder).unwrap();
+        let mut buffer = vec![];
+        builder.encode(&mut buffer).unwrap();
+
+        let beads = TypedBeads::new(&buffer, &types).ok().unwrap();
+        let mut de = super::Deserializer::new(&beads);
+        let strings: Vec<Option<&str>> = (0..3).map(|_| Option::<&str>::deserialize(&mut de).unwrap()).collect();
+        assert_eq!(strings, vec![Some("a"), None, Some("b")]);
+        assert_eq!(BTreeMap::<String, u8>::deserialize(&mut de).unwrap(), map);
+    }
+
+    #[test]
+    fn unsupported_values_are_reported() {
+        let types = BeadTypeSet::new(&[BeadType::U8]);
+        let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+        let error = to_builder(&[1u16, 300], &mut builder).err().unwrap();
+        assert!(matches!(error, BeadsError::UnsupportedValue(ref v) if v == "300"));
+
+        let mut buffer = vec![];
+        builder.encode(&mut buffer).unwrap();
+        let beads = TypedBeads::new(&buffer, &types).ok().unwrap();
+        let error = from_beads::<(u8, u8)>(&beads).err().unwrap();
+        assert!(matches!(error, BeadsError::IndexOutOfBounds { index: 1, len: 1 }));
+        let error = from_beads::<String>(&beads).err().unwrap();
+        assert!(matches!(error, BeadsError::TypeMismatch { found: BeadType::U8, expected: "string" }));
+    }
+
+    #[test]
+    fn damaged_buffers_are_reported() {
+        let types = BeadTypeSet::new(&[BeadType::None, BeadType::U8, BeadType::Utf8]);
+        let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+        to_builder(&[Some("Maxim"), None, Some("Alex")], &mut builder).unwrap();
+        let mut buffer = vec![];
+        builder.encode(&mut buffer).unwrap();
+        assert_eq!(from_beads::<Option<String>>(&TypedBeads::new(&buffer, &types).ok().unwrap()).unwrap().len(), 3);
+
+        buffer.truncate(buffer.len() - 2);
+        let beads = TypedBeads::new(&buffer, &types).ok().unwrap();
+        let error = from_beads::<Option<String>>(&beads).err().unwrap();
+        assert!(matches!(error, BeadsError::InvalidElement { index: 2, ref error } if matches!(**error, BeadsError::TruncatedBuffer { .. })));
+
+        // a broken tag of an optional value
+        buffer[1] = 0b11;
+        let beads = TypedBeads::new(&buffer, &types).ok().unwrap();
+        let error = from_beads::<Option<String>>(&beads).err().unwrap();
+        assert!(matches!(error, BeadsError::InvalidElement { index: 0, ref error } if matches!(**error, BeadsError::InvalidTag { .. })));
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::cmp::{min, max};
+use crate::bead_type::{BeadType, BeadTypeSet};
+use crate::reference::BeadReference;
+use crate::sequence::{TypedBeads, IndexedBeads, FixedSizeBeads, BitPackedBeads};
+use crate::vlq::VlqByteSize;
+use crate::error::BeadsError;
+
+/// Numbers and sizes of the elements of a `TypedBeads` sequence, see `TypedBeads::stats`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BeadsStats {
+    pub count: usize,
+    /// Number of elements per type.
+    pub type_counts: HashMap<BeadType, usize>,
+    /// Bytes of the element count and of the type set, if it is included.
+    pub header_bytes: usize,
+    pub tag_bytes: usize,
+    /// Bytes of the values, without the length prefixes of `Utf8` and `Bytes` values.
+    pub payload_bytes: usize,
+    /// Bytes of the VLQ length prefixes of `Utf8` and `Bytes` values.
+    pub length_prefix_bytes: usize,
+    /// Size of the encoded sequence, which also covers the padding byte of sequences without data.
+    pub total_bytes: usize,
+    pub uint_range: Option<(u128, u128)>,
+    pub int_range: Option<(i128, i128)>,
+    /// Smallest and largest float value, NaN is left out.
+    pub float_range: Option<(f64, f64)>,
+    /// Number of `Utf8` and `Bytes` values by length. Entry 0 counts empty values,
+    /// entry `k` the values with a length in `2^(k-1)..2^k`.
+    pub length_histogram: Vec<usize>,
+}
+
+impl BeadsStats {
+    pub fn count_of(&self, bead_type: BeadType) -> usize {
+        self.type_counts.get(&bead_type).copied().unwrap_or(0)
+    }
+
+    /// Adds the numbers of `other`, for example to sum up the stats of several columns.
+    pub fn merge(&mut self, other: &BeadsStats) {
+        self.count += other.count;
+        for (t, count) in other.type_counts.iter() {
+            *self.type_counts.entry(*t).or_insert(0) += count;
+        }
+        self.header_bytes += other.header_bytes;
+        self.tag_bytes += other.tag_bytes;
+        self.payload_bytes += other.payload_bytes;
+        self.length_prefix_bytes += other.length_prefix_bytes;
+        self.total_bytes += other.total_bytes;
+        if let Some((low, high)) = other.uint_range {
+            self.uint_range = Some(self.uint_range.map_or((low, high), |(l, h)| (min(l, low), max(h, high))));
+        }
+        if let Some((low, high)) = other.int_range {
+            self.int_range = Some(self.int_range.map_or((low, high), |(l, h)| (min(l, low), max(h, high))));
+        }
+        if let Some((low, high)) = other.float_range {
+            self.float_range = Some(self.float_range.map_or((low, high), |(l, h)| (l.min(low), h.max(high))));
+        }
+        if self.length_histogram.len() < other.length_histogram.len() {
+            self.length_histogram.resize(other.length_histogram.len(), 0);
+        }
+        for (i, count) in other.length_histogram.iter().enumerate() {
+            self.length_histogram[i] += count;
+        }
+    }
+
+    pub(crate) fn add_bead(&mut self, bead: &BeadReference) -> Result<(), BeadsError> {
+        self.count += 1;
+        *self.type_counts.entry(bead.bead_type).or_insert(0) += 1;
+        self.payload_bytes += bead.buffer.len();
+        if bead.is_string() || bead.is_bytes() {
+            let len = bead.buffer.len();
+            self.length_prefix_bytes += len.vlq_byte_size();
+            let bucket = (usize::BITS - len.leading_zeros()) as usize;
+            if self.length_histogram.len() <= bucket {
+                self.length_histogram.resize(bucket + 1, 0);
+            }
+            self.length_histogram[bucket] += 1;
+        } else if bead.is_uint() {
+            let v = bead.try_to_uint()?;
+            self.uint_range = Some(self.uint_range.map_or((v, v), |(l, h)| (min(l, v), max(h, v))));
+        } else if bead.is_int() {
+            let v = bead.try_to_int()?;
+            self.int_range = Some(self.int_range.map_or((v, v), |(l, h)| (min(l, v), max(h, v))));
+        } else if bead.is_float() {
+            let v = bead.try_to_float()?;
+            if !v.is_nan() {
+                self.float_range = Some(self.float_range.map_or((v, v), |(l, h)| (l.min(v), h.max(v))));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Describes how a buffer is structured, so `layout_stats` can walk nested sequences.
+#[derive(Debug, Clone)]
+pub enum Layout {
+    Typed(BeadTypeSet),
+    /// A `TypedBeads` sequence written with `encode_with_types`.
+    TypesIncluded,
+    FixedSize,
+    BitPacked,
+    /// An `IndexedBeads` sequence with one layout per element. Elements without a layout are `Raw`.
+    Indexed(Vec<Layout>),
+    /// An `IndexedBeads` sequence where all elements have the same layout.
+    IndexedOf(Box<Layout>),
+    Raw,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutStats {
+    Typed(BeadsStats),
+    FixedSize { size: usize, count: usize, total_bytes: usize },
+    BitPacked { bits: usize, count: usize, total_bytes: usize },
+    /// `index_bytes` covers the header and the end offsets of the elements.
+    Indexed { index_bytes: usize, total_bytes: usize, children: Vec<LayoutStats> },
+    Raw { total_bytes: usize },
+}
+
+impl LayoutStats {
+    pub fn total_bytes(&self) -> usize {
+        match self {
+            LayoutStats::Typed(stats) => stats.total_bytes,
+            LayoutStats::FixedSize { total_bytes, .. }
+            | LayoutStats::BitPacked { total_bytes, .. }
+            | LayoutStats::Indexed { total_bytes, .. }
+            | LayoutStats::Raw { total_bytes } => *total_bytes
+        }
+    }
+
+    /// Stats of all typed sequences in the tree, merged into one.
+    pub fn typed_summary(&self) -> BeadsStats {
+        let mut summary = BeadsStats::default();
+        self.merge_typed_into(&mut summary);
+        summary
+    }
+
+    fn merge_typed_into(&self, summary: &mut BeadsStats) {
+        match self {
+            LayoutStats::Typed(stats) => summary.merge(stats),
+            LayoutStats::Indexed { children, .. } => {
+                for child in children {
+                    child.merge_typed_into(summary);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Collects the stats of `buffer` and of every nested sequence described by `layout`.
+/// Errors of nested sequences are reported as `InvalidElement` with the index of the element.
+pub fn layout_stats(buffer: &[u8], layout: &Layout) -> Result<LayoutStats, BeadsError> {
+    match layout {
+        Layout::Typed(types) => Ok(LayoutStats::Typed(TypedBeads::new(buffer, types)?.stats()?)),
+        Layout::TypesIncluded => Ok(LayoutStats::Typed(TypedBeads::new_types_included(buffer)?.stats()?)),
+        Layout::FixedSize => {
+            let beads = FixedSizeBeads::new(buffer)?;
+            Ok(LayoutStats::FixedSize { size: beads.size(), count: beads.len(), total_bytes: buffer.len() })
+        }
+        Layout::BitPacked => {
+            let beads = BitPackedBeads::new(buffer)?;
+            Ok(LayoutStats::BitPacked { bits: beads.bits(), count: beads.len(), total_bytes: buffer.len() })
+        }
+        Layout::Indexed(_) | Layout::IndexedOf(_) => {
+            let beads = IndexedBeads::new(buffer)?;
+            let mut children = Vec::with_capacity(beads.len());
+            for index in 0..beads.len() {
+                let child_layout = match layout {
+                    Layout::Indexed(layouts) => layouts.get(index).unwrap_or(&Layout::Raw),
+                    Layout::IndexedOf(child) => child.as_ref(),
+                    _ => &Layout::Raw
+                };
+                let (start, end) = beads.bounds(index).map_err(|e| BeadsError::InvalidElement { index, error: Box::new(e) })?;
+                let offset = beads.values_offset() + start;
+                let stats = layout_stats(&buffer[offset..beads.values_offset() + end], child_layout)
+                    .map_err(|e| BeadsError::InvalidElement { index, error: Box::new(e.shift(offset)) })?;
+                children.push(stats);
+            }
+            Ok(LayoutStats::Indexed { index_bytes: beads.values_offset(), total_bytes: buffer.len(), children })
+        }
+        Layout::Raw => Ok(LayoutStats::Raw { total_bytes: buffer.len() })
+    }
+}
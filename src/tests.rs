@@ -1,8 +1,16 @@
-use crate::bead_type::{BeadType, BeadTypeSet};
-use crate::builder::{TypedBeadsBuilder, IndexedBeadsBuilder, FixedSizeBeadsBuilder, FixedSizeBeadsIncrementalUintBuilder};
-use crate::sequence::{TypedBeads, IndexedBeads, FixedSizeBeads, DedupBeads};
+#![allow(clippy::useless_vec, clippy::explicit_auto_deref, clippy::bool_assert_comparison)]
+
+use crate::bead_type::{BeadType, BeadTypeSet, TimeKind, TimeUnit};
+use crate::builder::{TypedBeadsBuilder, IndexedBeadsBuilder, FixedSizeBeadsBuilder, FixedSizeBeadsIncrementalUintBuilder, StreamingTypedBeadsBuilder, BeadsBuilder, DeltaBeadsBuilder, RleBeadsBuilder, BitPackedBeadsBuilder};
+use crate::sequence::{TypedBeads, IndexedBeads, FixedSizeBeads, DedupBeads, DeltaBeads, RleBeads, BitPackedBeads};
+use crate::stats::{Layout, LayoutStats, layout_stats};
 use crate::converters::beads_to_dedup_beads;
+use crate::error::BeadsError;
+use crate::reader::BeadsReader;
+use crate::reference::BeadReference;
 use std::f64;
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[test]
 fn bead_type_set() {
@@ -33,11 +41,11 @@ fn push_bool_beads_sequence() {
     builder.push_bool(false);
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![12, 116, 11]);
 
     out.clear();
-    builder.encode_with_types(&mut out);
+    builder.encode_with_types(&mut out).unwrap();
     assert_eq!(out, vec![6, 0, 0, 0, 12, 116, 11]);
 }
 
@@ -61,11 +69,11 @@ fn push_bool_beads_and_none_sequence() {
     builder.push_bool(false);
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![13, 133, 169, 105, 2]);
 
     out.clear();
-    builder.encode_with_types(&mut out);
+    builder.encode_with_types(&mut out).unwrap();
     assert_eq!(out, vec![7, 0, 0, 0, 13, 133, 169, 105, 2]);
 }
 
@@ -81,11 +89,11 @@ fn push_u8_beads_sequence() {
     builder.push_int(22);
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![4, 13, 20, 21, 22]);
 
     out.clear();
-    builder.encode_with_types(&mut out);
+    builder.encode_with_types(&mut out).unwrap();
     assert_eq!(out, vec![9, 0, 0, 0, 4, 13, 20, 21, 22]);
 }
 
@@ -100,7 +108,7 @@ fn push_u16_beads_sequence() {
     builder.push_int(22);
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![3, 2, 20, 5, 1, 22]);
 }
 
@@ -115,7 +123,7 @@ fn push_u32_beads_sequence() {
     builder.push_int(22);
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![3, 2, 20, 136, 251, 3, 0, 22]);
 }
 
@@ -130,7 +138,7 @@ fn push_vlq_beads_sequence() {
     builder.push_int(22);
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![3, 7, 20, 136, 247, 15, 22]);
 }
 #[test]
@@ -145,7 +153,7 @@ fn push_vlqz_beads_sequence() {
     builder.push_int(-22);
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![4, 15, 40, 144, 238, 31, 44, 43]);
 }
 
@@ -161,7 +169,7 @@ fn push_f32_beads_sequence() {
     builder.push_int(-22);
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![4, 15, 0, 0, 160, 65, 0, 226, 126, 72, 0, 0, 176, 65, 0, 0, 176, 193]);
 }
 
@@ -177,7 +185,7 @@ fn push_f64_beads_sequence() {
     builder.push_int(-22);
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![4, 15, 0, 0, 0, 0, 0, 0, 52, 64, 0, 0, 0, 0, 64, 220, 15, 65, 0, 0, 0, 0, 0, 0, 54, 64, 0, 0, 0, 0, 0, 0, 54, 192]);
 }
 
@@ -193,14 +201,43 @@ fn push_f16_beads_sequence() {
     builder.push_int(-22);
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![4, 2, 0, 77, 0, 226, 126, 72, 128, 77, 128, 205]);
 
     out.clear();
-    builder.encode_with_types(&mut out);
+    builder.encode_with_types(&mut out).unwrap();
     assert_eq!(out, vec![0, 96, 0, 0, 4, 2, 0, 77, 0, 226, 126, 72, 128, 77, 128, 205]);
 }
 
+#[test]
+fn failed_push_leaves_builder_unchanged() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::I8]);
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    let mut expected_builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    // the failing values would start a new tag byte
+    for i in 0..8 {
+        builder.push_int(i - 4);
+        expected_builder.push_int(i - 4);
+    }
+    let mut before = Vec::new();
+    builder.encode(&mut before).unwrap();
+    assert_eq!(builder.push_uint(300), false);
+    assert_eq!(builder.push_int(-403), false);
+    assert_eq!(builder.push_double(0.5), false);
+    let mut after = Vec::new();
+    builder.encode(&mut after).unwrap();
+    assert_eq!(after, before);
+
+    builder.push_int(-4);
+    expected_builder.push_int(-4);
+    let mut out = Vec::new();
+    builder.encode(&mut out).unwrap();
+    let mut expected = Vec::new();
+    expected_builder.encode(&mut expected).unwrap();
+    assert_eq!(out, expected);
+    assert_eq!(TypedBeads::new(out.as_slice(), &types).ok().unwrap().iter().map(|b| b.to_int()).collect::<Vec<i128>>(), vec![-4, -3, -2, -1, 0, 1, 2, 3, -4]);
+}
+
 #[test]
 fn push_i16_beads_sequence() {
     let mut builder = TypedBeadsBuilder::new(
@@ -212,7 +249,7 @@ fn push_i16_beads_sequence() {
     builder.push_int(-22);
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![3, 2, 20, 251, 254, 234]);
 }
 
@@ -230,7 +267,7 @@ fn push_many_ints() {
     }
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![14, 127, 1, 2, 3, 4, 5, 6, 7, 63, 8, 9, 10, 11, 12, 13]);
 }
 
@@ -247,11 +284,11 @@ fn push_many_ints_four_types() {
     }
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![14, 85, 1, 2, 3, 4, 21, 5, 6, 7, 85, 8, 9, 10, 11, 5, 12, 13]);
 
     out.clear();
-    builder.encode_with_types(&mut out);
+    builder.encode_with_types(&mut out).unwrap();
     assert_eq!(out, vec![1, 1, 3, 0, 14, 85, 1, 2, 3, 4, 21, 5, 6, 7, 85, 8, 9, 10, 11, 5, 12, 13]);
 }
 
@@ -268,11 +305,11 @@ fn push_many_ints_sixteen_types() {
     }
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![14, 34, 1, 2, 34, 3, 4, 34, 5, 6, 2, 7, 34, 8, 9, 34, 10, 11, 34, 12, 13]);
 
     out.clear();
-    builder.encode_with_types(&mut out);
+    builder.encode_with_types(&mut out).unwrap();
     assert_eq!(out, vec![3, 1, 3, 0, 14, 34, 1, 2, 34, 3, 4, 34, 5, 6, 2, 7, 34, 8, 9, 34, 10, 11, 34, 12, 13]);
 }
 
@@ -284,22 +321,22 @@ fn push_string() {
     builder.push_string("Maxim");
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![1, 1, 5, 77, 97, 120, 105, 109]);
 
     out.clear();
     builder.push_string("Hello 🤪");
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![2, 3, 5, 77, 97, 120, 105, 109, 10, 72, 101, 108, 108, 111, 32, 240, 159, 164, 170]);
 
     out.clear();
     builder.push_none();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![3, 3, 5, 77, 97, 120, 105, 109, 10, 72, 101, 108, 108, 111, 32, 240, 159, 164, 170]);
 
     out.clear();
     builder.push_string("Aha!");
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![4, 11, 5, 77, 97, 120, 105, 109, 10, 72, 101, 108, 108, 111, 32, 240, 159, 164, 170, 4, 65, 104, 97, 33]);
 }
 
@@ -311,17 +348,17 @@ fn push_string_and_bytes() {
     builder.push_string("Maxim");
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![1, 0, 5, 77, 97, 120, 105, 109]);
 
     out.clear();
     builder.push_bytes("Hello 🤪".as_bytes());
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![2, 2, 5, 77, 97, 120, 105, 109, 10, 72, 101, 108, 108, 111, 32, 240, 159, 164, 170]);
 
     out.clear();
     builder.push_bytes("Aha!".as_bytes());
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![3, 6, 5, 77, 97, 120, 105, 109, 10, 72, 101, 108, 108, 111, 32, 240, 159, 164, 170, 4, 65, 104, 97, 33]);
 }
 
@@ -339,7 +376,7 @@ who is thought to have scrambled parts of Cicero's De Finibus Bonorum et Malorum
 for use in a type specimen book."#);
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![1, 1, 170, 2,
                          10, 76, 111, 114, 101, 109, 32, 105, 112, 115, 117, 109, 44, 32,
                          111, 114, 32, 108, 105, 112, 115, 117, 109, 32,
@@ -390,7 +427,7 @@ fn roundtrip_strings() {
         builder.push_string(s);
     }
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
     for (index, b) in beads.iter().enumerate() {
@@ -409,7 +446,7 @@ fn roundtrip_strings_types_included() {
         builder.push_string(s);
     }
     let mut out = Vec::new();
-    builder.encode_with_types(&mut out);
+    builder.encode_with_types(&mut out).unwrap();
 
     let beads = TypedBeads::new_types_included(out.as_slice()).ok().unwrap();
     for (index, b) in beads.iter().enumerate() {
@@ -426,7 +463,7 @@ fn roundtrip_strings_3_types() {
         builder.push_string(s);
     }
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
     for (index, b) in beads.iter().enumerate() {
@@ -443,7 +480,7 @@ fn roundtrip_strings_5_types() {
         builder.push_string(s);
     }
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
     for (index, b) in beads.iter().enumerate() {
@@ -460,7 +497,7 @@ fn roundtrip_strings_5_types_types_included() {
         builder.push_string(s);
     }
     let mut out = Vec::new();
-    builder.encode_with_types(&mut out);
+    builder.encode_with_types(&mut out).unwrap();
 
     let beads = TypedBeads::new_types_included(out.as_slice()).ok().unwrap();
     for (index, b) in beads.iter().enumerate() {
@@ -477,7 +514,7 @@ fn non_symmetric_sequence() {
         builder.push_string(s);
     }
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
     assert_eq!(beads.is_symmetrical(), false);
@@ -492,7 +529,7 @@ fn symmetric_sequence_bool() {
         builder.push_bool(*b);
     }
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
     assert_eq!(beads.is_symmetrical(), true);
@@ -515,7 +552,7 @@ fn symmetric_sequence_bool_and_none() {
     }
     builder.push_none();
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
     assert_eq!(beads.is_symmetrical(), true);
@@ -539,7 +576,7 @@ fn symmetric_sequence_one_byte_numbers() {
         builder.push_int(*v);
     }
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
     assert_eq!(beads.is_symmetrical(), true);
@@ -563,7 +600,7 @@ fn symmetric_sequence_two_byte_numbers_3_types() {
         builder.push_int(*v);
     }
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
     assert_eq!(beads.is_symmetrical(), true);
@@ -587,7 +624,7 @@ fn symmetric_sequence_four_byte_numbers_3_types() {
         builder.push_int(*v);
     }
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
     assert_eq!(beads.is_symmetrical(), true);
@@ -611,7 +648,7 @@ fn symmetric_sequence_eight_byte_numbers_3_types() {
         builder.push_int(*v);
     }
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
     assert_eq!(beads.is_symmetrical(), true);
@@ -635,7 +672,7 @@ fn symmetric_sequence_sixteen_byte_numbers() {
         builder.push_int(*v);
     }
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
     assert_eq!(beads.is_symmetrical(), true);
@@ -650,6 +687,27 @@ fn symmetric_sequence_sixteen_byte_numbers() {
     assert_eq!(symb.get(4).unwrap().to_int(), -9);
 }
 
+#[test]
+fn roundtrip_sixteen_byte_numbers() {
+    let types = BeadTypeSet::new(&[BeadType::U128, BeadType::I128, BeadType::None]);
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    builder.push_uint(u128::MAX - 5);
+    builder.push_int(i128::MIN + 3);
+    builder.push_none();
+    builder.push_uint(7);
+    let mut out = Vec::new();
+    builder.encode(&mut out).unwrap();
+    assert_eq!(out.len(), 1 + 1 + 3 * 16);
+
+    let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
+    let mut iter = beads.iter();
+    assert_eq!(iter.next().unwrap().to_uint(), u128::MAX - 5);
+    assert_eq!(iter.next().unwrap().to_int(), i128::MIN + 3);
+    assert_eq!(iter.next().unwrap().is_none(), true);
+    assert_eq!(iter.next().unwrap().to_uint(), 7);
+    assert!(iter.next().is_none());
+}
+
 #[test]
 fn symmetric_sequence_four_byte_numbers_3_types_100_values() {
     let types = BeadTypeSet::new(&[BeadType::U32, BeadType::I32, BeadType::F32]);
@@ -658,7 +716,7 @@ fn symmetric_sequence_four_byte_numbers_3_types_100_values() {
         builder.push_int(v - 50);
     }
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
     assert_eq!(beads.is_symmetrical(), true);
@@ -680,7 +738,7 @@ fn non_symmetric_sequence_one_byte_numbers() {
         builder.push_int(*v);
     }
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
     assert_eq!(beads.is_symmetrical(), false);
@@ -698,11 +756,11 @@ fn push_single_type_sequence() {
     builder.push_int(22);
 
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
     assert_eq!(out, vec![3, 20, 21, 22]);
 
     out.clear();
-    builder.encode_with_types(&mut out);
+    builder.encode_with_types(&mut out).unwrap();
     assert_eq!(out, vec![8, 0, 0, 0, 3, 20, 21, 22]);
 }
 
@@ -715,7 +773,7 @@ fn roundtrip_single_type_string_sequence() {
         builder.push_string(*v);
     }
     let mut out = Vec::new();
-    builder.encode(&mut out);
+    builder.encode(&mut out).unwrap();
 
     let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
     assert_eq!(beads.is_symmetrical(), false);
@@ -734,7 +792,7 @@ fn roundtrip_single_type_int_sequence() {
         builder.push_int(v);
     }
     let mut out = Vec::new();
-    builder.encode_with_types(&mut out);
+    builder.encode_with_types(&mut out).unwrap();
 
     let beads = TypedBeads::new_types_included(out.as_slice()).ok().unwrap();
     assert_eq!(beads.is_symmetrical(), true);
@@ -759,7 +817,7 @@ fn roundtrip_type_priority() {
     }
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     let beads = TypedBeads::new(buffer.as_slice(), &types).ok().unwrap();
     let out_values: Vec<i128> = beads.iter().map(|b|{b.to_int()}).collect();
@@ -779,7 +837,7 @@ fn roundtrip_type_priority_3_type() {
     }
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     let beads = TypedBeads::new(buffer.as_slice(), &types).ok().unwrap();
     let out_values: Vec<i128> = beads.iter().map(|b|{b.to_int()}).collect();
@@ -799,7 +857,7 @@ fn roundtrip_type_priority_5_type() {
     }
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     let beads = TypedBeads::new(buffer.as_slice(), &types).ok().unwrap();
     let out_values: Vec<i128> = beads.iter().map(|b|{b.to_int()}).collect();
@@ -819,7 +877,7 @@ fn roundtrip_type_priority_plus_unfit_value() {
     }
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     let beads = TypedBeads::new(buffer.as_slice(), &types).ok().unwrap();
     let out_values: Vec<i128> = beads.iter().map(|b|{b.to_int()}).collect();
@@ -840,7 +898,7 @@ fn roundtrip_type_priority_plus_unfit_type() {
     }
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     let beads = TypedBeads::new(buffer.as_slice(), &types).ok().unwrap();
     let out_values: Vec<i128> = beads.iter().map(|b|{b.to_int()}).collect();
@@ -861,7 +919,7 @@ fn roundtrip_type_priority_5_types_plus_unfit_value() {
     }
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     let beads = TypedBeads::new(buffer.as_slice(), &types).ok().unwrap();
     let out_values: Vec<i128> = beads.iter().map(|b|{b.to_int()}).collect();
@@ -875,11 +933,11 @@ fn roundtrip_push_double_with_accuracy() {
         &types
     ).ok().unwrap();
     builder.push_double(0.1);
-    builder.push_double_with_accuracy(0.1, std::f32::EPSILON as f64);
+    builder.push_double_with_accuracy(0.1, f32::EPSILON as f64);
     builder.push_double_with_accuracy(0.1, 0.01);
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     assert_eq!(buffer, vec![
         3, 6,
@@ -898,14 +956,14 @@ fn indexed_beads_builder() {
     builder.push(&[1, 2, 3, 4]);
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     assert_eq!(buffer, &[8, 4, 1, 2, 3, 4]);
 
     builder.push(&[1, 7, 8, 5, 3, 4, 6, 7, 8, 5, 20]);
 
     buffer.clear();
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     assert_eq!(buffer, &[
         16,
@@ -923,7 +981,7 @@ fn roundtrip_indexed_beads_builder() {
     builder.push(&[1, 7, 8, 5, 3, 4, 6, 7, 8, 5, 20]);
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     let ib = IndexedBeads::new(buffer.as_slice()).ok().unwrap();
 
@@ -941,7 +999,7 @@ fn roundtrip_large_indexed_beads_builder() {
     }
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     let ib = IndexedBeads::new(buffer.as_slice()).ok().unwrap();
 
@@ -949,6 +1007,78 @@ fn roundtrip_large_indexed_beads_builder() {
     assert_eq!(ib[number_of_beads / 2].to_vec(), vec![1, 2, 3, 4]);
 }
 
+#[test]
+fn indexed_beads_iter_and_slice() {
+    let mut builder = IndexedBeadsBuilder::new();
+    builder.push(&[1, 2]);
+    builder.push(&[]);
+    builder.push(&[3]);
+    builder.push(&[4, 5, 6]);
+
+    let mut buffer: Vec<u8> = vec![];
+    builder.encode(&mut buffer).unwrap();
+    let ib = IndexedBeads::new(buffer.as_slice()).ok().unwrap();
+
+    let all: Vec<&[u8]> = ib.iter().collect();
+    assert_eq!(all, vec![&[1, 2][..], &[], &[3], &[4, 5, 6]]);
+    let reversed: Vec<&[u8]> = ib.iter().rev().collect();
+    assert_eq!(reversed, vec![&[4, 5, 6][..], &[3], &[], &[1, 2]]);
+    assert_eq!(ib.iter().len(), 4);
+    assert_eq!(ib.iter().nth(2), Some(&[3][..]));
+    assert_eq!((&ib).into_iter().count(), 4);
+    assert_eq!(ib.first(), Some(&[1, 2][..]));
+    assert_eq!(ib.last(), Some(&[4, 5, 6][..]));
+
+    let middle = ib.slice(1..3).ok().unwrap();
+    assert_eq!(middle.len(), 2);
+    assert_eq!(middle.get(0).ok().unwrap(), &[]);
+    assert_eq!(&middle[1], &[3]);
+    assert_eq!(middle.first(), Some(&[][..]));
+    assert_eq!(middle.last(), Some(&[3][..]));
+    assert!(matches!(middle.get(2).err().unwrap(), BeadsError::IndexOutOfBounds { index: 2, len: 2 }));
+
+    let tail = ib.slice(2..).ok().unwrap().slice(1..=1).ok().unwrap();
+    assert_eq!(tail.iter().collect::<Vec<&[u8]>>(), vec![&[4, 5, 6][..]]);
+    assert_eq!(ib.slice(..0).ok().unwrap().first(), None);
+    assert!(matches!(ib.slice(2..5).err().unwrap(), BeadsError::IndexOutOfBounds { index: 4, len: 4 }));
+
+    // elements outlive the `IndexedBeads` value they were read from
+    let first = IndexedBeads::new(buffer.as_slice()).ok().unwrap().get(0).ok().unwrap();
+    assert_eq!(first, &[1, 2]);
+}
+
+#[test]
+fn indexed_beads_typed_children() {
+    let mut typed = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::U8, BeadType::U16])).ok().unwrap();
+    typed.push_uint(3);
+    typed.push_uint(300);
+    let mut inner = IndexedBeadsBuilder::new();
+    inner.push(&[7]);
+    inner.push(&[8, 9]);
+    let mut fixed = FixedSizeBeadsBuilder::new(2);
+    fixed.push(&[1, 2]);
+    let builders: Vec<Box<dyn BeadsBuilder>> = vec![Box::new(typed), Box::new(inner), Box::new(fixed)];
+    let mut buffer: Vec<u8> = vec![];
+    IndexedBeadsBuilder::encode_from_beads_builders(&mut buffer, builders).unwrap();
+
+    let ib = IndexedBeads::new(buffer.as_slice()).ok().unwrap();
+    let typed = ib.get_typed(0, &BeadTypeSet::new(&[BeadType::U8, BeadType::U16])).ok().unwrap();
+    assert_eq!(typed.iter().map(|b| b.to_uint()).collect::<Vec<u128>>(), vec![3, 300]);
+    let inner = ib.get_indexed(1).ok().unwrap();
+    assert_eq!(inner.last(), Some(&[8, 9][..]));
+    assert_eq!(ib.get_fixed_size(2).ok().unwrap().get(0).ok().unwrap(), &[1, 2]);
+    assert!(matches!(ib.get_indexed(3).err().unwrap(), BeadsError::IndexOutOfBounds { index: 3, len: 3 }));
+
+    // errors of a child point into the enclosing buffer
+    let mut builder = IndexedBeadsBuilder::new();
+    builder.push(&[1, 2]);
+    builder.push(&[5]);
+    let mut buffer: Vec<u8> = vec![];
+    builder.encode(&mut buffer).unwrap();
+    let ib = IndexedBeads::new(buffer.as_slice()).ok().unwrap();
+    assert!(matches!(ib.get_typed(1, &BeadTypeSet::new(&[BeadType::U8])).err().unwrap(), BeadsError::TruncatedBuffer { offset: 6, needed: 1 }));
+}
+
 #[test]
 fn roundtrip_fixed_size_beads() {
     let mut builder = FixedSizeBeadsBuilder::new(3);
@@ -958,7 +1088,7 @@ fn roundtrip_fixed_size_beads() {
     builder.push(&[130, 150, 190]);
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     assert_eq!(buffer, vec![3, 1, 2, 3, 10, 20, 30, 30, 50, 90, 130, 150, 190]);
 
@@ -980,7 +1110,7 @@ fn roundtrip_fixed_size_beads_with_incremental_uint_builder() {
     builder.push(205);
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     assert_eq!(buffer, vec![1, 1, 2, 20, 205]);
 
@@ -996,7 +1126,7 @@ fn roundtrip_fixed_size_beads_with_incremental_uint_builder() {
     builder.push(340);
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     assert_eq!(buffer, vec![2, 1, 0, 2, 0, 20, 0, 205, 0, 84, 1]);
 
@@ -1010,6 +1140,63 @@ fn roundtrip_fixed_size_beads_with_incremental_uint_builder() {
     assert_eq!(fs_beads[4].to_vec(), vec![84, 1]);
 }
 
+#[test]
+fn fixed_size_beads_typed_accessors() {
+    let mut builder = FixedSizeBeadsIncrementalUintBuilder::new();
+    builder.push(1);
+    builder.push(70_000);
+    builder.push(5);
+
+    let mut buffer: Vec<u8> = vec![];
+    builder.encode(&mut buffer).unwrap();
+
+    let fs_beads = FixedSizeBeads::new(&buffer).ok().unwrap();
+    assert_eq!(fs_beads.size(), 3);
+    assert_eq!(fs_beads.get_u32(1).ok().unwrap(), 70_000);
+    assert_eq!(fs_beads.get_u64(2).ok().unwrap(), 5);
+    assert_eq!(fs_beads.get_u128(0).ok().unwrap(), 1);
+    assert!(matches!(fs_beads.get_u16(0).err().unwrap(), BeadsError::OutOfRange { target: "u16", .. }));
+    assert!(matches!(fs_beads.get_u32(3).err().unwrap(), BeadsError::IndexOutOfBounds { index: 3, len: 3 }));
+    assert_eq!(fs_beads.iter_uint().ok().unwrap().collect::<Vec<u128>>(), vec![1, 70_000, 5]);
+    assert_eq!(fs_beads.iter_uint().ok().unwrap().next_back(), Some(5));
+    assert_eq!(fs_beads.as_slice::<u32>(), None);
+
+    let wide = FixedSizeBeads::new(&[17, 1]).ok().unwrap();
+    assert!(matches!(wide.iter_uint().err().unwrap(), BeadsError::OutOfRange { target: "u128", .. }));
+    let empty = FixedSizeBeads::new(&[0]).ok().unwrap();
+    assert_eq!(empty.iter_uint().ok().unwrap().len(), 0);
+}
+
+#[test]
+fn fixed_size_beads_as_slice() {
+    let values: Vec<u32> = vec![1, 300, 70_000, u32::MAX];
+    let mut builder = FixedSizeBeadsBuilder::new(4);
+    for v in values.iter() {
+        builder.push(&v.to_le_bytes());
+    }
+    let mut encoded: Vec<u8> = vec![];
+    builder.encode(&mut encoded).unwrap();
+
+    // place the one byte header so that the entries are aligned for u32
+    let mut storage = vec![0u8; encoded.len() + 8];
+    let start = storage[1..].as_ptr().align_offset(4);
+    storage[start..start + encoded.len()].copy_from_slice(&encoded);
+    let aligned = FixedSizeBeads::new(&storage[start..start + encoded.len()]).ok().unwrap();
+    if cfg!(target_endian = "little") {
+        assert_eq!(aligned.as_slice::<u32>(), Some(values.as_slice()));
+    }
+    assert_eq!(aligned.as_slice::<u64>(), None);
+    assert_eq!(aligned.as_slice::<u16>(), None);
+
+    storage.copy_within(start..start + encoded.len(), start + 1);
+    let misaligned = FixedSizeBeads::new(&storage[start + 1..start + 1 + encoded.len()]).ok().unwrap();
+    assert_eq!(misaligned.as_slice::<u32>(), None);
+    assert_eq!(misaligned.get_u32(2).ok().unwrap(), 70_000);
+
+    let bytes = FixedSizeBeads::new(&[1, 7, 8, 9]).ok().unwrap();
+    assert_eq!(bytes.as_slice::<u8>(), Some(&[7u8, 8, 9][..]));
+}
+
 #[test]
 fn roundtrip_dedup_f64() {
     let mut builder = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::F64])).ok().unwrap();
@@ -1020,7 +1207,7 @@ fn roundtrip_dedup_f64() {
     builder.push_double(0.3);
 
     let mut buffer: Vec<u8> = vec![];
-    builder.encode(&mut buffer);
+    builder.encode(&mut buffer).unwrap();
 
     assert_eq!(buffer, vec![5, 154, 153, 153, 153, 153, 153, 185, 63, 154, 153, 153, 153, 153, 153, 185, 63, 51, 51, 51, 51, 51, 51, 211, 63, 154, 153, 153, 153, 153, 153, 201, 63, 51, 51, 51, 51, 51, 51, 211, 63]);
 
@@ -1030,11 +1217,1226 @@ fn roundtrip_dedup_f64() {
 
     assert_eq!(dedup_buffer, vec![16, 6, 34, 1, 0, 0, 1, 2, 1, 24, 8, 16, 24, 154, 153, 153, 153, 153, 153, 185, 63, 51, 51, 51, 51, 51, 51, 211, 63, 154, 153, 153, 153, 153, 153, 201, 63]);
 
-    let dedup = DedupBeads::new(dedup_buffer.as_slice());
-    assert_eq!(dedup.len().ok().unwrap(), 5);
+    let dedup = DedupBeads::new(dedup_buffer.as_slice()).ok().unwrap();
+    assert_eq!(dedup.len(), 5);
     assert_eq!(dedup.get(0).ok().unwrap(), <f64>::to_le_bytes(0.1).to_vec());
     assert_eq!(dedup.get(1).ok().unwrap(), <f64>::to_le_bytes(0.1).to_vec());
     assert_eq!(dedup.get(2).ok().unwrap(), <f64>::to_le_bytes(0.3).to_vec());
     assert_eq!(dedup.get(3).ok().unwrap(), <f64>::to_le_bytes(0.2).to_vec());
     assert_eq!(dedup.get(4).ok().unwrap(), <f64>::to_le_bytes(0.3).to_vec());
-}
\ No newline at end of file
+}
+
+#[test]
+fn dedup_beads_dictionary_and_typed_values() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::I16, BeadType::Utf8]);
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    builder.push_string("Max");
+    builder.push_uint(7);
+    builder.push_string("Max");
+    builder.push_int(-300);
+    builder.push_uint(7);
+
+    let mut buffer: Vec<u8> = vec![];
+    builder.encode(&mut buffer).unwrap();
+    let mut dedup_buffer = vec![];
+    beads_to_dedup_beads(&buffer, &types, &mut dedup_buffer).ok().unwrap();
+
+    let dedup = DedupBeads::new(dedup_buffer.as_slice()).ok().unwrap();
+    assert_eq!(dedup.len(), 5);
+    assert_eq!(dedup.get(2).ok().unwrap(), "Max".as_bytes());
+    assert_eq!((0..5).map(|i| dedup.code(i).ok().unwrap()).collect::<Vec<usize>>(), vec![0, 1, 0, 2, 1]);
+    assert_eq!(dedup.dictionary().len(), 3);
+    assert_eq!(dedup.iter().len(), 5);
+    assert_eq!(dedup.iter().next_back(), Some(&[7][..]));
+    assert_eq!(dedup.iter().collect::<Vec<&[u8]>>()[3], &(-300i16).to_le_bytes());
+
+    // a one byte string has the same payload size as a U8 value
+    assert!(matches!(dedup.get_bead(1, &types).err().unwrap(), BeadsError::AmbiguousTypes { .. }));
+    assert!(matches!(dedup.dictionary_beads(&types).err().unwrap(), BeadsError::AmbiguousTypes { .. }));
+    for ambiguous in [vec![BeadType::U8, BeadType::I8], vec![BeadType::TrueFlag, BeadType::FalseFlag, BeadType::None], vec![BeadType::None, BeadType::Utf8]] {
+        let error = dedup.get_bead(1, &BeadTypeSet::new(&ambiguous)).err().unwrap();
+        assert!(matches!(error, BeadsError::AmbiguousTypes { .. }));
+    }
+
+    let typed = BeadTypeSet::new(&[BeadType::U8, BeadType::I16, BeadType::None]);
+    let mut builder = TypedBeadsBuilder::new(&typed).ok().unwrap();
+    builder.push_none();
+    builder.push_uint(7);
+    builder.push_none();
+    builder.push_int(-300);
+    builder.push_uint(7);
+    let mut typed_beads: Vec<u8> = vec![];
+    builder.encode(&mut typed_beads).unwrap();
+    let mut typed_buffer = vec![];
+    beads_to_dedup_beads(&typed_beads, &typed, &mut typed_buffer).ok().unwrap();
+    let typed_dedup = DedupBeads::new(typed_buffer.as_slice()).ok().unwrap();
+    assert_eq!(typed_dedup.get_bead(1, &typed).ok().unwrap().to_uint(), 7);
+    assert_eq!(typed_dedup.get_bead(3, &typed).ok().unwrap().to_int(), -300);
+    assert!(typed_dedup.get_bead(0, &typed).ok().unwrap().is_none());
+    let dictionary = typed_dedup.dictionary_beads(&typed).ok().unwrap();
+    assert_eq!(dictionary.len(), 3);
+    assert_eq!(dictionary[typed_dedup.code(3).ok().unwrap()].to_int(), -300);
+    assert_eq!(dictionary[typed_dedup.code(4).ok().unwrap()].bead_type, BeadType::U8);
+
+    let error = dedup.get_bead(0, &BeadTypeSet::new(&[BeadType::U8, BeadType::F64])).err().unwrap();
+    assert!(matches!(error, BeadsError::UnparsableValue(_)));
+    assert!(matches!(dedup.get(5).err().unwrap(), BeadsError::IndexOutOfBounds { index: 5, len: 5 }));
+    assert!(DedupBeads::new(&buffer).is_err());
+}
+
+#[test]
+fn errors_carry_kind_and_offset() {
+    assert!(matches!(TypedBeadsBuilder::new(&BeadTypeSet::new(&[])).err().unwrap(), BeadsError::TypeSetSize(0)));
+
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::None]);
+    assert!(matches!(TypedBeads::new(&[], &types).err().unwrap(), BeadsError::InvalidVlq { offset: 0 }));
+    assert!(matches!(TypedBeads::new(&[3], &types).err().unwrap(), BeadsError::TruncatedBuffer { offset: 1, needed: 1 }));
+    assert!(matches!(TypedBeads::new_types_included(&[9, 0, 0]).err().unwrap(), BeadsError::TruncatedBuffer { offset: 0, needed: 5 }));
+    assert!(matches!(TypedBeads::new_types_included(&[9, 0, 0, 0, 128]).err().unwrap(), BeadsError::InvalidVlq { offset: 4 }));
+
+    let beads = TypedBeads::new(&[2, 1, 20], &types).ok().unwrap();
+    assert!(matches!(beads.symmetric().err().unwrap(), BeadsError::NotSymmetrical));
+
+    let beads = TypedBeads::new(&[2, 0, 20, 21], &BeadTypeSet::new(&[BeadType::U8, BeadType::I8])).ok().unwrap();
+    let symb = beads.symmetric().ok().unwrap();
+    assert!(matches!(symb.get(2).err().unwrap(), BeadsError::IndexOutOfBounds { index: 2, len: 2 }));
+
+    let mut builder = IndexedBeadsBuilder::new();
+    builder.push(&[1, 2, 3, 4]);
+    let mut buffer: Vec<u8> = vec![];
+    builder.encode(&mut buffer).unwrap();
+    let ib = IndexedBeads::new(buffer.as_slice()).ok().unwrap();
+    let error = ib.get(1).err().unwrap();
+    assert!(matches!(error, BeadsError::IndexOutOfBounds { index: 1, len: 1 }));
+    assert_eq!(error.to_string(), "Bad index: 1 where count is: 1");
+
+    buffer.truncate(4);
+    let ib = IndexedBeads::new(buffer.as_slice()).ok().unwrap();
+    let error = ib.get(0).err().unwrap();
+    assert!(matches!(error, BeadsError::TruncatedBuffer { offset: 2, needed: 4 }));
+    assert_eq!(error.offset(), Some(2));
+
+    let boxed: Box<dyn std::error::Error> = Box::new(error);
+    assert_eq!(boxed.to_string(), "Buffer is too short to read 4 byte(s) at offset 2");
+}
+
+#[test]
+fn try_to_accessors_report_errors() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::I8, BeadType::Utf8, BeadType::TrueFlag]);
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    builder.push_uint(200);
+    builder.push_int(-5);
+    builder.push_string("Maxim");
+    builder.push_bool(true);
+    let mut out = Vec::new();
+    builder.encode(&mut out).unwrap();
+
+    let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
+    let values: Vec<_> = beads.iter().collect();
+
+    assert_eq!(values[0].try_to_uint().unwrap(), 200);
+    assert_eq!(values[0].try_to_float().unwrap(), 200.0);
+    assert!(matches!(values[0].try_to_bool().err().unwrap(), BeadsError::TypeMismatch { found: BeadType::U8, expected: "bool" }));
+    assert!(matches!(values[1].try_to_uint().err().unwrap(), BeadsError::OutOfRange { target: "u128", .. }));
+    assert_eq!(values[1].try_to_int().unwrap(), -5);
+    assert_eq!(values[2].try_to_str().unwrap(), "Maxim");
+    assert!(matches!(values[2].try_to_int().err().unwrap(), BeadsError::TypeMismatch { found: BeadType::Utf8, expected: "int" }));
+    assert!(matches!(values[3].try_to_str().err().unwrap(), BeadsError::TypeMismatch { found: BeadType::TrueFlag, expected: "string" }));
+    assert_eq!(values[3].try_to_bool().unwrap(), true);
+}
+
+#[test]
+fn try_to_str_rejects_invalid_utf8() {
+    let types = BeadTypeSet::new(&[BeadType::Utf8]);
+    let beads = TypedBeads::new(&[1, 3, 65, 0xff, 66], &types).ok().unwrap();
+    let value = beads.iter().next().unwrap();
+    assert!(matches!(value.try_to_str().err().unwrap(), BeadsError::InvalidUtf8 { offset: 1 }));
+    assert!(matches!(String::try_from(value).err().unwrap(), BeadsError::InvalidUtf8 { offset: 1 }));
+}
+
+#[test]
+fn try_from_reports_reason() {
+    let types = BeadTypeSet::new(&[BeadType::U16, BeadType::None]);
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    builder.push_uint(300);
+    builder.push_none();
+    let mut out = Vec::new();
+    builder.encode(&mut out).unwrap();
+
+    let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
+    let mut iter = beads.iter();
+    let error = u8::try_from(iter.next().unwrap()).err().unwrap();
+    assert!(matches!(error, BeadsError::OutOfRange { ref value, target: "u8" } if value == "300"));
+    assert_eq!(error.to_string(), "Value 300 does not fit into u8");
+    let error = u16::try_from(iter.next().unwrap()).err().unwrap();
+    assert!(matches!(error, BeadsError::TypeMismatch { found: BeadType::None, expected: "u16" }));
+}
+
+#[test]
+fn validate_builder_output() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::I8, BeadType::None, BeadType::Utf8, BeadType::U128]);
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    for i in 0..50 {
+        match i % 4 {
+            0 => builder.push_none(),
+            1 => builder.push_int(-(i as i128)),
+            2 => builder.push_string("Maxim"),
+            _ => builder.push_uint(u128::MAX - i),
+        };
+    }
+    let mut out = Vec::new();
+    builder.encode(&mut out).unwrap();
+    let beads = TypedBeads::new(out.as_slice(), &types).ok().unwrap();
+    assert!(beads.validate().is_ok());
+    assert_eq!(beads.iter().count(), 50);
+    assert_eq!(beads.iter().nth(47).unwrap().to_uint(), u128::MAX - 47);
+
+    out.clear();
+    builder.encode_with_types(&mut out).unwrap();
+    assert!(TypedBeads::new_types_included(out.as_slice()).ok().unwrap().validate().is_ok());
+}
+
+#[test]
+fn validate_after_unfit_value_on_tag_boundary() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::I8]);
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    for i in 0..8 {
+        builder.push_int(i);
+    }
+    assert_eq!(builder.push_int(403), false);
+    let mut out = Vec::new();
+    builder.encode(&mut out).unwrap();
+    assert_eq!(out, vec![8, 255, 0, 1, 2, 3, 4, 5, 6, 7]);
+    assert!(TypedBeads::new(out.as_slice(), &types).ok().unwrap().validate().is_ok());
+}
+
+#[test]
+fn validate_reports_first_offending_element() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::None, BeadType::Utf8]);
+
+    let beads = TypedBeads::new(&[3, 0b00_10_01, 5, 2, 65, 66], &types).ok().unwrap();
+    assert!(beads.validate().is_ok());
+
+    let beads = TypedBeads::new(&[3, 0b11_10_01, 5, 2, 65, 66], &types).ok().unwrap();
+    let error = beads.validate().err().unwrap();
+    assert!(matches!(error, BeadsError::InvalidElement { index: 2, ref error } if matches!(**error, BeadsError::InvalidTag { offset: 1, tag: 3, types: 3 })));
+
+    let beads = TypedBeads::new(&[3, 0b00_10_01, 5, 4, 65, 66], &types).ok().unwrap();
+    let error = beads.validate().err().unwrap();
+    assert!(matches!(error, BeadsError::InvalidElement { index: 1, ref error } if matches!(**error, BeadsError::TruncatedBuffer { offset: 4, needed: 4 })));
+    assert_eq!(error.offset(), Some(4));
+
+    let beads = TypedBeads::new(&[3, 0b00_10_01, 5, 2, 65, 0xff], &types).ok().unwrap();
+    let error = beads.validate().err().unwrap();
+    assert!(matches!(error, BeadsError::InvalidElement { index: 1, ref error } if matches!(**error, BeadsError::InvalidUtf8 { offset: 5 })));
+
+    let beads = TypedBeads::new(&[3, 0b00_10_01, 5, 2, 65, 66, 0], &types).ok().unwrap();
+    assert!(matches!(beads.validate().err().unwrap(), BeadsError::TrailingBytes { offset: 6, count: 1 }));
+
+    let beads = TypedBeads::new(&[9, 0b00_10_01, 5, 2, 65, 66], &types).ok().unwrap();
+    assert!(matches!(beads.validate().err().unwrap(), BeadsError::InvalidElement { index: 4, .. }));
+}
+
+#[test]
+fn iterator_len_is_exact_only_for_valid_sequences() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::None, BeadType::Utf8]);
+    let beads = TypedBeads::new(&[3, 0b00_10_01, 5, 2, 65, 66], &types).ok().unwrap();
+    assert!(beads.validate().is_ok());
+    assert_eq!(beads.iter().len(), beads.iter().count());
+
+    // the third tag is broken, the header still claims three elements
+    let beads = TypedBeads::new(&[3, 0b11_10_01, 5, 2, 65, 66], &types).ok().unwrap();
+    assert!(beads.validate().is_err());
+    let mut iter = beads.iter();
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.by_ref().count(), 2);
+    assert_eq!(iter.len(), 0);
+}
+
+struct FailingWriter {
+    capacity: usize
+}
+
+impl std::io::Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.capacity == 0 {
+            return Err(std::io::Error::new(std::io::ErrorKind::WriteZero, "disk full"));
+        }
+        let n = std::cmp::min(self.capacity, buf.len());
+        self.capacity -= n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn encode_returns_bytes_written() {
+    let mut builder = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Utf8, BeadType::None])).ok().unwrap();
+    builder.push_string("Maxim");
+    builder.push_none();
+
+    let mut out = Vec::new();
+    assert_eq!(builder.encode(&mut out).unwrap(), out.len());
+    out.clear();
+    assert_eq!(builder.encode_with_types(&mut out).unwrap(), out.len());
+
+    let mut indexed = IndexedBeadsBuilder::new();
+    indexed.push(&[1, 2, 3]);
+    out.clear();
+    assert_eq!(indexed.encode(&mut out).unwrap(), out.len());
+
+    let mut fixed = FixedSizeBeadsIncrementalUintBuilder::new();
+    fixed.push(300);
+    out.clear();
+    assert_eq!(fixed.encode(&mut out).unwrap(), 3);
+    assert_eq!(out.len(), 3);
+}
+
+#[test]
+fn encode_passes_io_errors_through() {
+    let mut builder = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Utf8])).ok().unwrap();
+    builder.push_string("Maxim");
+
+    let error = builder.encode(&mut FailingWriter { capacity: 3 }).err().unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::WriteZero);
+    assert!(builder.encode_with_types(&mut FailingWriter { capacity: 2 }).is_err());
+
+    let mut out = Vec::new();
+    builder.encode(&mut out).unwrap();
+    let error = beads_to_dedup_beads(&out, &BeadTypeSet::new(&[BeadType::Utf8]), &mut FailingWriter { capacity: 4 }).err().unwrap();
+    assert!(matches!(error, BeadsError::Io(ref e) if e.kind() == std::io::ErrorKind::WriteZero));
+}
+
+#[test]
+fn roundtrip_incremental_uint_builder_with_zeros_only() {
+    let mut builder = FixedSizeBeadsIncrementalUintBuilder::new();
+    builder.push(0);
+    builder.push(0);
+
+    let mut buffer: Vec<u8> = vec![];
+    builder.encode(&mut buffer).unwrap();
+    assert_eq!(buffer, vec![1, 0, 0]);
+    assert_eq!(FixedSizeBeads::new(&buffer).ok().unwrap().len(), 2);
+
+    let mut values = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Utf8])).ok().unwrap();
+    values.push_string("Max");
+    values.push_string("Max");
+    let mut buffer: Vec<u8> = vec![];
+    values.encode(&mut buffer).unwrap();
+    let mut dedup_buffer = vec![];
+    beads_to_dedup_beads(&buffer, &BeadTypeSet::new(&[BeadType::Utf8]), &mut dedup_buffer).ok().unwrap();
+    let dedup = DedupBeads::new(dedup_buffer.as_slice()).ok().unwrap();
+    assert_eq!(dedup.len(), 2);
+    assert_eq!(dedup.get(1).ok().unwrap(), "Max".as_bytes().to_vec());
+}
+
+#[test]
+fn builder_from_encoded_continues_sequence() {
+    let type_sets = vec![
+        BeadTypeSet::new(&[BeadType::U16]),
+        BeadTypeSet::new(&[BeadType::None]),
+        BeadTypeSet::new(&[BeadType::U8, BeadType::None]),
+        BeadTypeSet::new(&[BeadType::U8, BeadType::I8, BeadType::None, BeadType::Utf8]),
+        BeadTypeSet::new(&[BeadType::U8, BeadType::I16, BeadType::None, BeadType::Utf8, BeadType::F32, BeadType::TrueFlag]),
+    ];
+    fn push(builder: &mut TypedBeadsBuilder, i: i128) {
+        match i % 6 {
+            0 => builder.push_none(),
+            1 => builder.push_int(-i % 200),
+            2 => builder.push_string("Hello"),
+            3 => builder.push_bool(true),
+            4 => builder.push_double(0.5),
+            _ => builder.push_uint((i % 300) as u128),
+        };
+    }
+    for types in type_sets {
+        for split in vec![0, 1, 5, 8, 13, 100] {
+            let mut expected = TypedBeadsBuilder::new(&types).ok().unwrap();
+            let mut first = TypedBeadsBuilder::new(&types).ok().unwrap();
+            for i in 0..split {
+                push(&mut expected, i);
+                push(&mut first, i);
+            }
+            let mut buffer = vec![];
+            first.encode(&mut buffer).unwrap();
+            let mut with_types = vec![];
+            first.encode_with_types(&mut with_types).unwrap();
+
+            let mut continued = TypedBeadsBuilder::from_encoded(&buffer, &types).ok().unwrap();
+            let mut continued_with_types = TypedBeadsBuilder::from_encoded_types_included(&with_types).ok().unwrap();
+            for i in split..split + 50 {
+                push(&mut expected, i);
+                push(&mut continued, i);
+                push(&mut continued_with_types, i);
+            }
+            let mut expected_buffer = vec![];
+            expected.encode(&mut expected_buffer).unwrap();
+            let mut continued_buffer = vec![];
+            continued.encode(&mut continued_buffer).unwrap();
+            assert_eq!(continued_buffer, expected_buffer);
+            let mut expected_buffer = vec![];
+            expected.encode_with_types(&mut expected_buffer).unwrap();
+            let mut continued_buffer = vec![];
+            continued_with_types.encode_with_types(&mut continued_buffer).unwrap();
+            assert_eq!(continued_buffer, expected_buffer);
+        }
+    }
+}
+
+#[test]
+fn builder_from_encoded_rejects_broken_buffers() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::Utf8]);
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    builder.push_string("Hi");
+    builder.push_uint(3);
+    let mut buffer = vec![];
+    builder.encode(&mut buffer).unwrap();
+
+    buffer.push(0);
+    let error = TypedBeadsBuilder::from_encoded(&buffer, &types).err().unwrap();
+    assert!(matches!(error, BeadsError::TrailingBytes { offset: 6, count: 1 }));
+    buffer.truncate(4);
+    let error = TypedBeadsBuilder::from_encoded(&buffer, &types).err().unwrap();
+    assert!(matches!(error, BeadsError::InvalidElement { index: 0, .. }));
+    assert!(matches!(TypedBeadsBuilder::from_encoded_types_included(&[1, 0]).err().unwrap(), BeadsError::TruncatedBuffer { .. }));
+
+    // unused tag bits set by another encoder don't leak into the appended values
+    let mut builder = TypedBeadsBuilder::from_encoded(&[1, 0b1111_1111, 5], &BeadTypeSet::new(&[BeadType::U8, BeadType::None])).ok().unwrap();
+    builder.push_none();
+    let mut buffer = vec![];
+    builder.encode(&mut buffer).unwrap();
+    assert_eq!(buffer, vec![2, 1, 5]);
+}
+
+#[test]
+fn builder_append_and_push_bead() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::None]);
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    builder.push_uint(1);
+    builder.push_none();
+    builder.push_uint(2);
+    let mut before = vec![];
+    builder.encode(&mut before).unwrap();
+
+    let other_types = BeadTypeSet::new(&[BeadType::U16, BeadType::None]);
+    let mut other = TypedBeadsBuilder::new(&other_types).ok().unwrap();
+    other.push_uint(3);
+    other.push_none();
+    other.push_uint(300);
+    let mut other_buffer = vec![];
+    other.encode(&mut other_buffer).unwrap();
+    let other_beads = TypedBeads::new(&other_buffer, &other_types).ok().unwrap();
+
+    let error = builder.append(&other_beads).err().unwrap();
+    assert!(matches!(&error, BeadsError::InvalidElement { index: 2, error } if matches!(error.as_ref(), BeadsError::UnsupportedValue(v) if v == "300")));
+    let mut after = vec![];
+    builder.encode(&mut after).unwrap();
+    assert_eq!(after, before);
+
+    let first = other_beads.iter().next().unwrap();
+    assert_eq!(builder.push_bead(&first), true);
+    assert_eq!(builder.push_bead(&other_beads.get(2).ok().unwrap()), false);
+    let mut after = vec![];
+    builder.encode(&mut after).unwrap();
+    assert_eq!(after, vec![4, 0b1101, 1, 2, 3]);
+}
+
+#[test]
+fn slice_and_split_match_rebuilt_sequences() {
+    let type_sets = vec![
+        BeadTypeSet::new(&[BeadType::U16]),
+        BeadTypeSet::new(&[BeadType::Utf8]),
+        BeadTypeSet::new(&[BeadType::None]),
+        BeadTypeSet::new(&[BeadType::U8, BeadType::None]),
+        BeadTypeSet::new(&[BeadType::U8, BeadType::I8, BeadType::None, BeadType::Utf8]),
+        BeadTypeSet::new(&[BeadType::U8, BeadType::I16, BeadType::None, BeadType::Utf8, BeadType::F32, BeadType::TrueFlag]),
+    ];
+    fn encoded(types: &BeadTypeSet, from: i128, to: i128) -> Vec<u8> {
+        let mut builder = TypedBeadsBuilder::new(types).ok().unwrap();
+        for i in from..to {
+            let pushed = match i % 6 {
+                0 => builder.push_none(),
+                1 => builder.push_int(-i % 100),
+                2 => builder.push_string(&"s".repeat((i % 5) as usize)),
+                3 => builder.push_bool(true),
+                _ => builder.push_uint((i % 200) as u128),
+            };
+            // exactly one element per value, whatever the types are
+            let _ = pushed || builder.push_uint((i % 200) as u128) || builder.push_string("f") || builder.push_none();
+        }
+        let mut buffer = vec![];
+        builder.encode(&mut buffer).unwrap();
+        buffer
+    }
+    for types in type_sets {
+        let buffer = encoded(&types, 0, 40);
+        let beads = TypedBeads::new(&buffer, &types).ok().unwrap();
+        for (start, end) in vec![(0, 40), (0, 0), (0, 5), (8, 16), (8, 11), (2, 3), (3, 37), (39, 40), (40, 40)] {
+            let mut out = vec![];
+            let written = beads.slice(start..end, &mut out).ok().unwrap();
+            assert_eq!(written, out.len());
+            assert_eq!(out, encoded(&types, start as i128, end as i128), "{}..{}", start, end);
+        }
+        let (head, tail) = beads.split_at(17).ok().unwrap();
+        assert_eq!(head, encoded(&types, 0, 17));
+        assert_eq!(tail, encoded(&types, 17, 40));
+    }
+}
+
+#[test]
+fn slice_reports_bad_ranges_and_elements() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::Utf8]);
+    let beads = TypedBeads::new(&[3, 0b010, 1, 5, 104, 2], &types).ok().unwrap();
+    let mut out = vec![];
+    assert!(matches!(beads.slice(1..4, &mut out).err().unwrap(), BeadsError::IndexOutOfBounds { index: 3, len: 3 }));
+    assert!(matches!(beads.split_at(4).err().unwrap(), BeadsError::IndexOutOfBounds { index: 4, len: 3 }));
+    let error = beads.slice(0..3, &mut out).err().unwrap();
+    assert!(matches!(error, BeadsError::InvalidElement { index: 1, .. }));
+    assert!(beads.slice(0..1, &mut out).is_ok());
+}
+
+#[test]
+fn typed_beads_stats() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::I16, BeadType::None, BeadType::Utf8, BeadType::F32]);
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    builder.push_uint(7);
+    builder.push_uint(200);
+    builder.push_int(-300);
+    builder.push_none();
+    builder.push_string("");
+    builder.push_string("Hello");
+    builder.push_string(&"x".repeat(200));
+    builder.push_double(0.5);
+    builder.push_double(-1.25);
+    let mut buffer = vec![];
+    builder.encode_with_types(&mut buffer).unwrap();
+
+    let stats = TypedBeads::new_types_included(&buffer).ok().unwrap().stats().ok().unwrap();
+    assert_eq!(stats.count, 9);
+    assert_eq!(stats.count_of(BeadType::U8), 2);
+    assert_eq!(stats.count_of(BeadType::Utf8), 3);
+    assert_eq!(stats.count_of(BeadType::U16), 0);
+    assert_eq!(stats.header_bytes, 5);
+    assert_eq!(stats.tag_bytes, 5);
+    assert_eq!(stats.payload_bytes, 1 + 1 + 2 + 5 + 200 + 4 + 4);
+    assert_eq!(stats.length_prefix_bytes, 1 + 1 + 2);
+    assert_eq!(stats.total_bytes, buffer.len());
+    assert_eq!(stats.total_bytes, stats.header_bytes + stats.tag_bytes + stats.payload_bytes + stats.length_prefix_bytes);
+    assert_eq!(stats.uint_range, Some((7, 200)));
+    assert_eq!(stats.int_range, Some((-300, -300)));
+    assert_eq!(stats.float_range, Some((-1.25, 0.5)));
+    assert_eq!(stats.length_histogram, vec![1, 0, 0, 1, 0, 0, 0, 0, 1]);
+
+    let nones = TypedBeads::new(&[200, 1, 0], &BeadTypeSet::new(&[BeadType::None])).ok().unwrap().stats().ok().unwrap();
+    assert_eq!(nones.count_of(BeadType::None), 200);
+    assert_eq!(nones.total_bytes, 3);
+
+    let error = TypedBeads::new(&[2, 0b0100_0001, 5, 104], &types).ok().unwrap().stats().err().unwrap();
+    assert!(matches!(error, BeadsError::InvalidElement { index: 1, .. }));
+}
+
+#[test]
+fn layout_stats_walks_nested_sequences() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::Utf8]);
+    let mut names = TypedBeadsBuilder::new(&types).ok().unwrap();
+    names.push_string("Max");
+    names.push_uint(3);
+    let mut ids = FixedSizeBeadsBuilder::new(2);
+    ids.push(&[1, 0]);
+    ids.push(&[2, 0]);
+    ids.push(&[3, 0]);
+    let mut nested = IndexedBeadsBuilder::new();
+    let mut chunk = vec![];
+    names.encode(&mut chunk).unwrap();
+    nested.push(&chunk);
+    nested.push(&chunk);
+    let builders: Vec<Box<dyn BeadsBuilder>> = vec![Box::new(names), Box::new(ids), Box::new(nested)];
+    let mut buffer: Vec<u8> = vec![];
+    IndexedBeadsBuilder::encode_from_beads_builders(&mut buffer, builders).unwrap();
+
+    let layout = Layout::Indexed(vec![Layout::Typed(types), Layout::FixedSize, Layout::IndexedOf(Box::new(Layout::Typed(types)))]);
+    let stats = layout_stats(&buffer, &layout).ok().unwrap();
+    assert_eq!(stats.total_bytes(), buffer.len());
+    let summary = stats.typed_summary();
+    assert_eq!(summary.count, 6);
+    assert_eq!(summary.count_of(BeadType::Utf8), 3);
+    assert_eq!(summary.uint_range, Some((3, 3)));
+    match stats {
+        LayoutStats::Indexed { index_bytes, children, .. } => {
+            assert_eq!(index_bytes, 4);
+            assert_eq!(children[1], LayoutStats::FixedSize { size: 2, count: 3, total_bytes: 7 });
+            assert!(matches!(&children[2], LayoutStats::Indexed { children, .. } if children.len() == 2));
+        }
+        _ => panic!("not indexed")
+    }
+    assert!(matches!(layout_stats(&buffer, &Layout::Indexed(vec![])).ok().unwrap(), LayoutStats::Indexed { .. }));
+
+    // the offset of a broken nested element points into the whole buffer
+    let layout = Layout::Indexed(vec![Layout::Typed(BeadTypeSet::new(&[BeadType::Bytes]))]);
+    let error = layout_stats(&buffer, &layout).err().unwrap();
+    assert!(matches!(error, BeadsError::InvalidElement { index: 0, .. }));
+    assert!(error.offset().unwrap() > 4);
+}
+
+#[test]
+fn timestamps_and_durations() {
+    let types = BeadTypeSet::new(&[BeadType::I8, BeadType::I64, BeadType::None]).with_time(TimeKind::Timestamp(TimeUnit::Millis));
+    assert_eq!(types.size(), 3);
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    let now = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123) + Duration::from_micros(456);
+    assert!(builder.push_timestamp(now));
+    assert!(builder.push_timestamp(UNIX_EPOCH + Duration::from_millis(5)));
+    // times before the epoch are rounded down to a whole unit
+    assert!(builder.push_timestamp(UNIX_EPOCH - Duration::from_micros(1500)));
+    assert!(builder.push_none());
+    assert_eq!(builder.push_duration(Duration::from_secs(1)), false);
+    let mut buffer = vec![];
+    builder.encode_with_types(&mut buffer).unwrap();
+    assert_eq!(buffer[3], 0b0000_0010);
+
+    let beads = TypedBeads::new_types_included(&buffer).ok().unwrap();
+    assert_eq!(beads.time(), Some(TimeKind::Timestamp(TimeUnit::Millis)));
+    let unit = beads.time().unwrap().unit();
+    assert_eq!(beads.get(0).ok().unwrap().to_timestamp(unit), UNIX_EPOCH + Duration::from_millis(1_700_000_000_123));
+    assert_eq!(beads.get(1).ok().unwrap().to_int(), 5);
+    assert_eq!(beads.get(2).ok().unwrap().to_timestamp(unit), UNIX_EPOCH - Duration::from_millis(2));
+    assert!(matches!(beads.get(3).ok().unwrap().try_to_timestamp(unit).err().unwrap(), BeadsError::TypeMismatch { .. }));
+    let reader = BeadsReader::new_types_included(buffer.as_slice()).ok().unwrap();
+    assert_eq!(reader.time(), Some(TimeKind::Timestamp(TimeUnit::Millis)));
+    // the time kind survives appending to the buffer
+    let mut builder = TypedBeadsBuilder::from_encoded_types_included(&buffer).ok().unwrap();
+    assert!(builder.push_timestamp(now));
+    let mut appended = vec![];
+    builder.encode_with_types(&mut appended).unwrap();
+    assert_eq!(appended[..4], buffer[..4]);
+
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::U32]).with_time(TimeKind::Duration(TimeUnit::Micros));
+    assert_eq!(types.time(), Some(TimeKind::Duration(TimeUnit::Micros)));
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    assert!(builder.push_duration(Duration::from_nanos(200_999)));
+    assert!(builder.push_duration(Duration::from_secs(60)));
+    assert_eq!(builder.push_timestamp(now), false);
+    let mut buffer = vec![];
+    builder.encode(&mut buffer).unwrap();
+    let beads = TypedBeads::new(&buffer, &types).ok().unwrap();
+    assert_eq!(beads.time(), Some(TimeKind::Duration(TimeUnit::Micros)));
+    assert_eq!(beads.get(0).ok().unwrap().to_duration(TimeUnit::Micros), Duration::from_micros(200));
+    assert_eq!(beads.get(1).ok().unwrap().to_duration(TimeUnit::Seconds), Duration::from_secs(60_000_000));
+
+    // plain type sets and sets read from old buffers have no time kind
+    assert_eq!(BeadTypeSet::new(&[BeadType::I64]).time(), None);
+    let mut builder = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::I64])).ok().unwrap();
+    assert_eq!(builder.push_timestamp(now), false);
+    let huge = BeadReference { value: 0, buffer: &u128::MAX.to_le_bytes(), bead_type: BeadType::U128 };
+    assert!(matches!(huge.try_to_duration(TimeUnit::Seconds).err().unwrap(), BeadsError::OutOfRange { .. }));
+    let huge = BeadReference { value: 0, buffer: &i128::MIN.to_le_bytes(), bead_type: BeadType::I128 };
+    assert!(matches!(huge.try_to_timestamp(TimeUnit::Nanos).err().unwrap(), BeadsError::OutOfRange { .. }));
+}
+
+#[test]
+fn decimals_with_scale() {
+    let types = BeadTypeSet::new(&[BeadType::I8, BeadType::I16, BeadType::None]).with_scale(2).ok().unwrap();
+    assert_eq!((types.size(), types.scale(), types.time()), (3, Some(2), None));
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    // the mantissa is pushed as a plain int, so 1.27 still fits into I8
+    assert!(builder.push_decimal("1.27".parse().ok().unwrap()));
+    assert!(builder.push_decimal("-0.5".parse().ok().unwrap()));
+    assert!(builder.push_decimal("3.000".parse().ok().unwrap()));
+    assert!(!builder.push_decimal("0.125".parse().ok().unwrap()));
+    assert!(builder.push_none());
+    let mut buffer = vec![];
+    builder.encode_with_types(&mut buffer).unwrap();
+
+    let beads = TypedBeads::new_types_included(&buffer).ok().unwrap();
+    assert_eq!(beads.scale(), Some(2));
+    assert_eq!(beads.get(0).ok().unwrap().bead_type, BeadType::I8);
+    assert_eq!(beads.get(0).ok().unwrap().to_int(), 127);
+    let decimals: Vec<String> = (0..3).map(|i| beads.get_decimal(i).ok().unwrap().to_string()).collect();
+    assert_eq!(decimals, ["1.27", "-0.50", "3.00"]);
+    assert!(matches!(beads.get_decimal(3).err().unwrap(), BeadsError::TypeMismatch { .. }));
+    assert_eq!(BeadsReader::new_types_included(buffer.as_slice()).ok().unwrap().scale(), Some(2));
+    // the scale survives appending to the buffer
+    let mut builder = TypedBeadsBuilder::from_encoded_types_included(&buffer).ok().unwrap();
+    assert!(builder.push_decimal("0.01".parse().ok().unwrap()));
+    let mut appended = vec![];
+    builder.encode_with_types(&mut appended).unwrap();
+    assert_eq!(appended[..4], buffer[..4]);
+
+    // scale 0 is a scale too, and it can be combined with a time kind
+    let types = BeadTypeSet::new(&[BeadType::I64]).with_scale(0).ok().unwrap().with_time(TimeKind::Duration(TimeUnit::Seconds));
+    assert_eq!((types.scale(), types.time()), (Some(0), Some(TimeKind::Duration(TimeUnit::Seconds))));
+    assert!(BeadTypeSet::new(&[BeadType::I64]).with_scale(16).is_err());
+
+    // sequences without scale take no decimals and can't be read as decimals
+    let mut builder = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::I64])).ok().unwrap();
+    assert!(!builder.push_decimal("1.5".parse().ok().unwrap()));
+    assert!(builder.push_int(15));
+    let mut buffer = vec![];
+    builder.encode(&mut buffer).unwrap();
+    let beads = TypedBeads::new(&buffer, &BeadTypeSet::new(&[BeadType::I64])).ok().unwrap();
+    assert_eq!(beads.scale(), None);
+    assert!(matches!(beads.get_decimal(0).err().unwrap(), BeadsError::MissingScale));
+}
+
+#[test]
+fn nested_sequences_as_elements() {
+    let row_types = BeadTypeSet::new(&[BeadType::U8, BeadType::U16]);
+    let mut rows = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Bytes, BeadType::None])).ok().unwrap();
+    for row in 0..4u128 {
+        let mut list = TypedBeadsBuilder::new(&row_types).ok().unwrap();
+        for v in 0..row * 100 {
+            list.push_uint(v);
+        }
+        assert!(rows.push_beads(&list));
+    }
+    let mut words = IndexedBeadsBuilder::new();
+    words.push(b"nested");
+    words.push(b"beads");
+    assert!(rows.push_beads(&words));
+    let mut codes = FixedSizeBeadsBuilder::new(2);
+    codes.push(&[1, 2]);
+    assert!(rows.push_beads(&codes));
+    assert!(rows.push_none());
+    let mut buffer = vec![];
+    rows.encode(&mut buffer).unwrap();
+
+    let beads = TypedBeads::new(&buffer, &BeadTypeSet::new(&[BeadType::Bytes, BeadType::None])).ok().unwrap();
+    assert_eq!(beads.len(), 7);
+    for (row, bead) in beads.iter().take(4).enumerate() {
+        let list = bead.as_typed_beads(&row_types).ok().unwrap();
+        assert_eq!(list.len(), row * 100);
+        assert!(list.iter().enumerate().all(|(i, v)| v.to_uint() == i as u128));
+    }
+    let words = beads.get(4).ok().unwrap().as_indexed_beads().ok().unwrap();
+    assert_eq!(words.get(1).ok().unwrap(), b"beads");
+    let codes = beads.get(5).ok().unwrap().as_fixed_size_beads().ok().unwrap();
+    assert_eq!(codes.get_u16(0).ok().unwrap(), 0x0201);
+    assert!(matches!(beads.get(6).ok().unwrap().as_indexed_beads().err().unwrap(), BeadsError::TypeMismatch { found: BeadType::None, .. }));
+    assert!(beads.get(0).ok().unwrap().as_typed_beads(&row_types).ok().unwrap().validate().is_ok());
+
+    // a sequence with its types can be stored as bytes and read without knowing them
+    let mut typed = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Utf8])).ok().unwrap();
+    typed.push_string("inner");
+    let mut with_types = vec![];
+    typed.encode_with_types(&mut with_types).unwrap();
+    let mut outer = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Bytes])).ok().unwrap();
+    outer.push_bytes(&with_types);
+    assert_eq!(outer.push_beads(&typed), true);
+    let mut buffer = vec![];
+    outer.encode(&mut buffer).unwrap();
+    let outer = TypedBeads::new(&buffer, &BeadTypeSet::new(&[BeadType::Bytes])).ok().unwrap();
+    let inner = outer.get(0).ok().unwrap().as_typed_beads_types_included().ok().unwrap();
+    assert_eq!(inner.get(0).ok().unwrap().to_str(), "inner");
+    assert_eq!(outer.get(1).ok().unwrap().as_typed_beads(&BeadTypeSet::new(&[BeadType::Utf8])).ok().unwrap().get(0).ok().unwrap().to_str(), "inner");
+
+    // without Bytes in the type set there is no way to store the nested sequence
+    let mut builder = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Utf8])).ok().unwrap();
+    assert_eq!(builder.push_beads(&typed), false);
+    let mut buffer = vec![];
+    builder.encode(&mut buffer).unwrap();
+    assert_eq!(buffer, vec![0, 0]);
+}
+
+#[test]
+fn delta_beads_round_trip() {
+    let types = BeadTypeSet::new(&[BeadType::I8, BeadType::VlqZ]);
+    let ids: Vec<i128> = (0..1000).map(|i| 4_000_000_000 + i * 3 + (i % 7)).collect();
+    let mut builder = DeltaBeadsBuilder::new(&types).ok().unwrap();
+    let mut plain = TypedBeadsBuilder::new(&types).ok().unwrap();
+    for id in ids.iter() {
+        assert!(builder.push_int(*id));
+        assert!(plain.push_int(*id));
+    }
+    let mut buffer = vec![];
+    let written = builder.encode(&mut buffer).unwrap();
+    assert_eq!(written, buffer.len());
+    assert_eq!(BeadsBuilder::len(&builder), buffer.len());
+    let mut plain_buffer = vec![];
+    plain.encode(&mut plain_buffer).unwrap();
+    assert!(buffer.len() * 4 < plain_buffer.len());
+    assert_eq!(buffer[0], 1);
+
+    let beads = DeltaBeads::new(&buffer, &types).ok().unwrap();
+    assert_eq!(beads.len(), 1000);
+    assert_eq!(beads.is_delta_of_delta(), false);
+    assert_eq!(beads.iter().len(), 1000);
+    assert_eq!(beads.iter().collect::<Vec<i128>>(), ids);
+    assert_eq!(beads.to_vec().ok().unwrap(), ids);
+
+    // differences wrap around, so the extremes can follow each other
+    let extremes = vec![i128::MIN, i128::MAX, 0, -1, i128::MIN];
+    let mut builder = DeltaBeadsBuilder::with_delta_of_delta(&BeadTypeSet::new(&[BeadType::I128])).ok().unwrap();
+    for v in extremes.iter() {
+        assert!(builder.push_int(*v));
+    }
+    let mut buffer = vec![];
+    builder.encode_with_types(&mut buffer).unwrap();
+    let beads = DeltaBeads::new_types_included(&buffer).ok().unwrap();
+    assert!(beads.is_delta_of_delta());
+    assert_eq!(beads.to_vec().ok().unwrap(), extremes);
+
+    // a value which does not fit leaves the builder as it was
+    let mut builder = DeltaBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::I8])).ok().unwrap();
+    assert!(builder.push_int(10));
+    assert_eq!(builder.push_int(200), false);
+    assert!(builder.push_int(-100));
+    let mut buffer = vec![];
+    builder.encode(&mut buffer).unwrap();
+    assert_eq!(buffer, vec![1, 2, 10, 146]);
+}
+
+#[test]
+fn delta_of_delta_timestamps() {
+    let types = BeadTypeSet::new(&[BeadType::I8, BeadType::I64]).with_time(TimeKind::Timestamp(TimeUnit::Millis));
+    let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let times: Vec<SystemTime> = (0..100u64).map(|i| start + Duration::from_millis(i * 1000 + if i == 50 { 3 } else { 0 })).collect();
+    let mut builder = DeltaBeadsBuilder::with_delta_of_delta(&types).ok().unwrap();
+    for t in times.iter() {
+        assert!(builder.push_timestamp(*t));
+    }
+    let mut buffer = vec![];
+    builder.encode_with_types(&mut buffer).unwrap();
+    // order, types, count, tags, the first value as I64 and the first delta, 1000, as I64 as well
+    assert_eq!(buffer.len(), 1 + 4 + 1 + 13 + 8 + 8 + 98);
+
+    let beads = DeltaBeads::new_types_included(&buffer).ok().unwrap();
+    let unit = beads.time().unwrap().unit();
+    let decoded: Vec<SystemTime> = beads.iter().map(|v| unit.timestamp(v).ok().unwrap()).collect();
+    assert_eq!(decoded, times);
+
+    let mut builder = DeltaBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::I64])).ok().unwrap();
+    assert_eq!(builder.push_timestamp(start), false);
+}
+
+#[test]
+fn delta_beads_report_broken_buffers() {
+    assert!(matches!(DeltaBeads::new(&[], &BeadTypeSet::new(&[BeadType::I8])).err().unwrap(), BeadsError::TruncatedBuffer { offset: 0, needed: 1 }));
+    assert!(matches!(DeltaBeads::new(&[3, 1, 5], &BeadTypeSet::new(&[BeadType::I8])).err().unwrap(), BeadsError::OutOfRange { .. }));
+    assert!(matches!(DeltaBeads::new(&[1, 1], &BeadTypeSet::new(&[BeadType::I8])).err().unwrap(), BeadsError::TruncatedBuffer { offset: 2, .. }));
+
+    // the third element is None, which is no difference
+    let types = BeadTypeSet::new(&[BeadType::I8, BeadType::None]);
+    let beads = DeltaBeads::new(&[1, 4, 0b1011, 5, 6, 7], &types).ok().unwrap();
+    assert_eq!(beads.iter().collect::<Vec<i128>>(), vec![5, 11]);
+    let error = beads.to_vec().err().unwrap();
+    assert!(matches!(&error, BeadsError::InvalidElement { index: 2, error } if matches!(**error, BeadsError::TypeMismatch { .. })));
+    let beads = DeltaBeads::new(&[1, 2, 0b0101, 5], &types).ok().unwrap();
+    assert!(matches!(beads.to_vec().err().unwrap(), BeadsError::InvalidElement { index: 1, .. }));
+
+    // a delta sequence can be nested like any other builder
+    let mut deltas = DeltaBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::U8])).ok().unwrap();
+    deltas.push_int(7);
+    let mut outer = TypedBeadsBuilder::new(&BeadTypeSet::new(&[BeadType::Bytes])).ok().unwrap();
+    assert!(outer.push_beads(&deltas));
+    let mut buffer = vec![];
+    outer.encode(&mut buffer).unwrap();
+    assert_eq!(buffer, vec![1, 3, 1, 1, 7]);
+}
+
+#[test]
+fn rle_beads_round_trip() {
+    let types = BeadTypeSet::new(&[BeadType::None, BeadType::TrueFlag, BeadType::FalseFlag, BeadType::U8, BeadType::Utf8]);
+    let mut builder = RleBeadsBuilder::new(&types).ok().unwrap();
+    let mut plain = TypedBeadsBuilder::new(&types).ok().unwrap();
+    let mut expected: Vec<String> = vec![];
+    for i in 0..1000 {
+        match i / 100 {
+            0 | 5 => { builder.push_none(); plain.push_none(); expected.push("none".to_string()); }
+            1 => { builder.push_bool(true); plain.push_bool(true); expected.push("true".to_string()); }
+            2 | 3 => { builder.push_string("open"); plain.push_string("open"); expected.push("open".to_string()); }
+            4 => { builder.push_uint(i / 30); plain.push_uint(i / 30); expected.push((i / 30).to_string()); }
+            _ => { builder.push_bool(false); plain.push_bool(false); expected.push("false".to_string()); }
+        }
+    }
+    assert_eq!(builder.runs(), 9);
+    assert_eq!(builder.push_double(0.5), false);
+    assert_eq!(builder.runs(), 9);
+    let mut buffer = vec![];
+    builder.encode(&mut buffer).unwrap();
+    assert_eq!(BeadsBuilder::len(&builder), buffer.len());
+    let mut plain_buffer = vec![];
+    plain.encode(&mut plain_buffer).unwrap();
+    assert!(buffer.len() * 10 < plain_buffer.len());
+
+    let to_text = |b: BeadReference| -> String {
+        if b.is_none() { "none".to_string() } else if b.is_bool() { b.to_bool().to_string() } else if b.is_string() { b.to_str().to_string() } else { b.to_uint().to_string() }
+    };
+    let beads = RleBeads::new(&buffer, &types).ok().unwrap();
+    assert_eq!(beads.len(), 1000);
+    assert_eq!(beads.runs(), 9);
+    assert_eq!(beads.iter().len(), 1000);
+    assert_eq!(beads.iter().map(to_text).collect::<Vec<String>>(), expected);
+    for i in [0, 99, 100, 250, 399, 400, 420, 450, 499, 500, 999] {
+        assert_eq!(to_text(beads.get(i).ok().unwrap()), expected[i]);
+    }
+    let mut iter = beads.iter();
+    assert_eq!(to_text(iter.nth(420).unwrap()), expected[420]);
+    assert_eq!(iter.len(), 579);
+    assert_eq!(to_text(iter.nth(200).unwrap()), expected[621]);
+    assert!(iter.nth(1000).is_none());
+    assert!(matches!(beads.get(1000).err().unwrap(), BeadsError::IndexOutOfBounds { index: 1000, len: 1000 }));
+
+    let mut buffer = vec![];
+    builder.encode_with_types(&mut buffer).unwrap();
+    let beads = RleBeads::new_types_included(&buffer).ok().unwrap();
+    assert_eq!(to_text(beads.get(999).ok().unwrap()), "false");
+
+    // an empty builder gives an empty sequence
+    let mut buffer = vec![];
+    RleBeadsBuilder::new(&types).ok().unwrap().encode(&mut buffer).unwrap();
+    let beads = RleBeads::new(&buffer, &types).ok().unwrap();
+    assert_eq!(beads.len(), 0);
+    assert!(beads.iter().next().is_none());
+}
+
+#[test]
+fn rle_builder_len_matches_encoded_length() {
+    let types = BeadTypeSet::new(&[BeadType::None, BeadType::U8, BeadType::U16]);
+    let mut builder = RleBeadsBuilder::new(&types).ok().unwrap();
+    let encoded_len = |builder: &RleBeadsBuilder| {
+        let mut buffer = vec![];
+        builder.encode(&mut buffer).unwrap()
+    };
+    assert_eq!(BeadsBuilder::len(&builder), encoded_len(&builder));
+    // run lengths which need more VLQ bytes and enough runs for wider index entries
+    for i in 0..20_000u128 {
+        builder.push_uint(if i < 17_000 { 7 } else { i });
+        if [1, 127, 128, 16_383, 16_384, 17_000, 17_300, 19_999].contains(&i) {
+            assert_eq!(BeadsBuilder::len(&builder), encoded_len(&builder), "{}", i);
+        }
+    }
+    builder.push_none();
+    assert_eq!(BeadsBuilder::len(&builder), encoded_len(&builder));
+}
+
+#[test]
+fn rle_beads_report_broken_buffers() {
+    let types = BeadTypeSet::new(&[BeadType::U8]);
+    let encode = |values: &[u8], lengths: &[u8]| -> Vec<u8> {
+        let mut indexed = IndexedBeadsBuilder::new();
+        indexed.push(values);
+        indexed.push(lengths);
+        let mut buffer = vec![];
+        indexed.encode(&mut buffer).unwrap();
+        buffer
+    };
+    let buffer = encode(&[2, 7, 8], &[2, 3, 1]);
+    let beads = RleBeads::new(&buffer, &types).ok().unwrap();
+    assert_eq!(beads.iter().map(|b| b.to_uint()).collect::<Vec<u128>>(), vec![7, 7, 7, 8]);
+
+    let error = RleBeads::new(&encode(&[2, 7, 8], &[1, 3]), &types).err().unwrap();
+    assert!(matches!(error, BeadsError::ColumnLengthMismatch { column: 1, len: 1, rows: 2 }));
+    let error = RleBeads::new(&encode(&[2, 7, 8], &[2, 3, 0]), &types).err().unwrap();
+    assert!(matches!(error, BeadsError::InvalidElement { index: 1, .. }));
+    let error = RleBeads::new(&encode(&[2, 7, 8], &[2, 3, 0x80]), &types).err().unwrap();
+    assert!(matches!(error, BeadsError::InvalidElement { index: 1, .. }));
+    assert_eq!(error.offset(), Some(8));
+    let error = RleBeads::new(&encode(&[2, 7], &[2, 3, 1]), &types).err().unwrap();
+    assert!(matches!(error, BeadsError::InvalidElement { index: 1, .. }));
+    assert!(RleBeads::new(&encode(&[2, 7, 8], &[2, 3, 1]), &BeadTypeSet::new(&[BeadType::U16])).is_err());
+}
+
+#[test]
+fn bit_packed_beads_round_trip() {
+    let spans: [(u128, u128); 8] = [(5, 0), (5, 1), (1000, 300), (0, u64::MAX as u128 >> 1), (0, u64::MAX as u128), (7, u64::MAX as u128 + 1), (0, u128::MAX), (u128::MAX - 3, 3)];
+    for (start, span) in spans {
+        // the first value is the smallest, value 50 the largest
+        let values: Vec<u128> = (0..100u128).map(|i| start + if i == 50 { span } else if span == 0 { 0 } else { (i * 7919) % span }).collect();
+        let mut builder = BitPackedBeadsBuilder::new();
+        for v in values.iter() {
+            builder.push(*v);
+        }
+        let bits = (128 - span.leading_zeros()) as usize;
+        assert_eq!(builder.bits(), bits);
+        let mut buffer = vec![];
+        assert_eq!(builder.encode(&mut buffer).unwrap(), buffer.len());
+        assert_eq!(BeadsBuilder::len(&builder), buffer.len());
+
+        let beads = BitPackedBeads::new(&buffer).ok().unwrap();
+        assert_eq!(beads.len(), 100);
+        assert_eq!(beads.bits(), bits);
+        assert_eq!(beads.reference(), start);
+        assert_eq!(beads.iter().len(), 100);
+        assert_eq!(beads.iter().collect::<Vec<u128>>(), values);
+        for (i, v) in values.iter().enumerate() {
+            assert_eq!(beads.get(i).ok().unwrap(), *v);
+        }
+        assert!(matches!(beads.get(100).err().unwrap(), BeadsError::IndexOutOfBounds { index: 100, len: 100 }));
+    }
+
+    // 300 codes take 9 bits each
+    let mut builder = BitPackedBeadsBuilder::new();
+    for i in 0..300 {
+        builder.push(i);
+    }
+    let mut buffer = vec![];
+    builder.encode(&mut buffer).unwrap();
+    assert_eq!(buffer.len(), 2 + 1 + 1 + 338);
+
+    let mut buffer = vec![];
+    BitPackedBeadsBuilder::new().encode(&mut buffer).unwrap();
+    assert_eq!(buffer, vec![0, 0, 0]);
+    let beads = BitPackedBeads::new(&buffer).ok().unwrap();
+    assert_eq!(beads.len(), 0);
+    assert!(beads.iter().next().is_none());
+}
+
+#[test]
+fn bit_packed_beads_report_broken_buffers() {
+    assert!(matches!(BitPackedBeads::new(&[2, 0, 129]).err().unwrap(), BeadsError::OutOfRange { .. }));
+    assert!(matches!(BitPackedBeads::new(&[2, 0]).err().unwrap(), BeadsError::TruncatedBuffer { offset: 2, .. }));
+    assert!(matches!(BitPackedBeads::new(&[3, 0, 4, 0x21]).err().unwrap(), BeadsError::TruncatedBuffer { offset: 3, needed: 2 }));
+    assert!(matches!(BitPackedBeads::new(&[2, 0, 4, 0x21, 7]).err().unwrap(), BeadsError::TrailingBytes { offset: 4, count: 1 }));
+    // the reference plus the bits of a value does not fit into a u128
+    let mut buffer = vec![1];
+    buffer.extend_from_slice(&[0xff; 18]);
+    buffer.extend_from_slice(&[0x03, 2, 3]);
+    let beads = BitPackedBeads::new(&buffer).ok().unwrap();
+    assert_eq!(beads.reference(), u128::MAX);
+    assert!(matches!(beads.get(0).err().unwrap(), BeadsError::OutOfRange { .. }));
+    assert!(beads.iter().next().is_none());
+}
+
+#[test]
+fn streaming_builder_matches_encode() {
+    let type_sets = vec![
+        BeadTypeSet::new(&[BeadType::U16]),
+        BeadTypeSet::new(&[BeadType::U8, BeadType::None]),
+        BeadTypeSet::new(&[BeadType::U8, BeadType::I8, BeadType::None, BeadType::Utf8]),
+        BeadTypeSet::new(&[BeadType::U8, BeadType::I16, BeadType::None, BeadType::Utf8, BeadType::F32, BeadType::TrueFlag]),
+    ];
+    for types in type_sets {
+        let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+        let mut cursor = std::io::Cursor::new(vec![7, 7, 7]);
+        cursor.set_position(3);
+        let mut streaming = StreamingTypedBeadsBuilder::new(&types, cursor).ok().unwrap();
+        let mut pushed = 0;
+        for i in 0..20_000i128 {
+            let (a, b) = match i % 7 {
+                0 => (builder.push_none(), streaming.push_none().unwrap()),
+                1 => (builder.push_int(-i % 200), streaming.push_int(-i % 200).unwrap()),
+                2 => (builder.push_string("Hello"), streaming.push_string("Hello").unwrap()),
+                3 => (builder.push_bool(true), streaming.push_bool(true).unwrap()),
+                4 => (builder.push_double(0.5), streaming.push_double(0.5).unwrap()),
+                _ => (builder.push_uint((i % 300) as u128), streaming.push_uint((i % 300) as u128).unwrap()),
+            };
+            assert_eq!(a, b);
+            if a {
+                pushed += 1;
+            }
+        }
+        assert_eq!(streaming.len(), pushed);
+
+        let mut encoded = vec![];
+        builder.encode(&mut encoded).unwrap();
+        let (cursor, written) = streaming.finish().unwrap();
+        assert_eq!(cursor.position() as usize, cursor.get_ref().len());
+        let streamed = &cursor.get_ref()[3..];
+        assert_eq!(written, streamed.len());
+
+        let beads = TypedBeads::new(streamed, &types).ok().unwrap();
+        assert!(beads.validate().is_ok());
+        let expected = TypedBeads::new(&encoded, &types).ok().unwrap();
+        assert_eq!(beads.len(), pushed);
+        assert_eq!(expected.len(), pushed);
+        assert_eq!(&streamed[10..], &encoded[encoded.len() - (streamed.len() - 10)..]);
+    }
+}
+
+#[test]
+fn streaming_builder_empty_and_with_types() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::Utf8]);
+    let streaming = StreamingTypedBeadsBuilder::new(&types, std::io::Cursor::new(vec![])).ok().unwrap();
+    let (cursor, written) = streaming.finish().unwrap();
+    assert_eq!(written, 11);
+    assert_eq!(TypedBeads::new(cursor.get_ref(), &types).ok().unwrap().len(), 0);
+
+    let mut streaming = StreamingTypedBeadsBuilder::new_with_types(&types, std::io::Cursor::new(vec![])).ok().unwrap();
+    streaming.push_string("Maxim").unwrap();
+    streaming.push_uint(42).unwrap();
+    let (cursor, written) = streaming.finish().unwrap();
+    assert_eq!(written, cursor.get_ref().len());
+    let beads = TypedBeads::new_types_included(cursor.get_ref()).ok().unwrap();
+    assert!(beads.validate().is_ok());
+    let values: Vec<String> = beads.iter().map(|b| if b.is_string() { b.to_str().to_string() } else { b.to_uint().to_string() }).collect();
+    assert_eq!(values, vec!["Maxim", "42"]);
+}
+
+#[test]
+fn streaming_builder_types_without_data() {
+    for types in [BeadTypeSet::new(&[BeadType::None]), BeadTypeSet::new(&[BeadType::TrueFlag])] {
+        let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+        let mut streaming = StreamingTypedBeadsBuilder::new(&types, std::io::Cursor::new(vec![])).ok().unwrap();
+        for _ in 0..5000 {
+            builder.push_none();
+            builder.push_bool(true);
+            streaming.push_none().unwrap();
+            streaming.push_bool(true).unwrap();
+        }
+        let mut encoded = vec![];
+        builder.encode(&mut encoded).unwrap();
+        assert_eq!(encoded, vec![0x88, 0x27, 0]);
+        let (cursor, written) = streaming.finish().unwrap();
+        assert_eq!(written, 11);
+        let beads = TypedBeads::new(cursor.get_ref(), &types).ok().unwrap();
+        assert!(beads.validate().is_ok());
+        assert_eq!(beads.len(), 5000);
+        assert_eq!(beads.iter().count(), 5000);
+    }
+}
+
+#[test]
+fn failed_push_keeps_single_type_data() {
+    let types = BeadTypeSet::new(&[BeadType::U16]);
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    assert_eq!(builder.push_uint(5), true);
+    assert_eq!(builder.push_int(-8), false);
+    let mut buffer = vec![];
+    builder.encode(&mut buffer).unwrap();
+    assert_eq!(buffer, vec![1, 5, 0]);
+}
+
+#[test]
+fn get_with_and_without_seek_index() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::Vlq, BeadType::None, BeadType::Utf8]);
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    for i in 0..500u128 {
+        match i % 4 {
+            0 => builder.push_none(),
+            1 => builder.push_string(&"a".repeat((i % 11) as usize)),
+            _ => builder.push_uint(i * i),
+        };
+    }
+    let mut buffer = vec![];
+    builder.encode(&mut buffer).unwrap();
+
+    let mut beads = TypedBeads::new(&buffer, &types).ok().unwrap();
+    let expected: Vec<(bool, Vec<u8>, u128)> = beads.iter().map(|b| (b.is_none(), b.to_bytes().to_vec(), if b.is_uint() { b.to_uint() } else { 0 })).collect();
+    let check = |beads: &TypedBeads| {
+        for (i, e) in expected.iter().enumerate() {
+            let b = beads.get(i).ok().unwrap();
+            assert_eq!((b.is_none(), b.to_bytes().to_vec(), if b.is_uint() { b.to_uint() } else { 0 }), *e);
+        }
+        assert!(matches!(beads.get(500), Err(BeadsError::IndexOutOfBounds { index: 500, len: 500 })));
+    };
+    check(&beads);
+    for step in vec![1, 3, 8, 64, 1000] {
+        beads.build_seek_index(step).ok().unwrap();
+        check(&beads);
+    }
+}
+
+#[test]
+fn seek_index_reports_broken_element() {
+    let types = BeadTypeSet::new(&[BeadType::U8, BeadType::Utf8]);
+    let mut builder = TypedBeadsBuilder::new(&types).ok().unwrap();
+    builder.push_uint(1);
+    builder.push_string("Maxim");
+    let mut buffer = vec![];
+    builder.encode(&mut buffer).unwrap();
+    buffer.truncate(buffer.len() - 1);
+
+    let mut beads = TypedBeads::new(&buffer, &types).ok().unwrap();
+    let error = beads.build_seek_index(1).err().unwrap();
+    assert!(matches!(error, BeadsError::InvalidElement { index: 1, .. }));
+    assert_eq!(error.offset(), Some(4));
+
+    let nones = [200, 1, 0];
+    let mut beads = TypedBeads::new(&nones, &BeadTypeSet::new(&[BeadType::None])).ok().unwrap();
+    beads.build_seek_index(4).ok().unwrap();
+    assert_eq!(beads.get(199).ok().unwrap().is_none(), true);
+}
+
+fn mixed_sequence(types: &BeadTypeSet, count: u128) -> Vec<u8> {
+    let mut builder = TypedBeadsBuilder::new(types).ok().unwrap();
+    for i in 0..count {
+        match i % 5 {
+            0 => builder.push_none(),
+            1 => builder.push_string(&"b".repeat((i % 13) as usize)),
+            _ => builder.push_uint(i * 7),
+        };
+    }
+    let mut buffer = vec![];
+    builder.encode(&mut buffer).unwrap();
+    buffer
+}
+
+fn describe_bead(b: crate::reference::BeadReference) -> (bool, Vec<u8>) {
+    (b.is_none(), b.to_bytes().to_vec())
+}
+
+#[test]
+fn iterator_len_nth_and_last() {
+    let type_sets = vec![
+        BeadTypeSet::new(&[BeadType::U16]),
+        BeadTypeSet::new(&[BeadType::Vlq]),
+        BeadTypeSet::new(&[BeadType::U8, BeadType::U16, BeadType::None, BeadType::Utf8]),
+    ];
+    for types in type_sets {
+        let buffer = mixed_sequence(&types, 300);
+        let mut beads = TypedBeads::new(&buffer, &types).ok().unwrap();
+        let all: Vec<(bool, Vec<u8>)> = beads.iter().map(describe_bead).collect();
+        for with_index in vec![false, true] {
+            if with_index {
+                beads.build_seek_index(16).ok().unwrap();
+            }
+            let mut iter = beads.iter();
+            assert_eq!(iter.len(), all.len());
+            iter.next();
+            assert_eq!(iter.size_hint(), (all.len() - 1, Some(all.len() - 1)));
+            assert_eq!(iter.nth(40).map(describe_bead), Some(all[41].clone()));
+            assert_eq!(iter.len(), all.len() - 42);
+            assert_eq!(iter.next().map(describe_bead), Some(all[42].clone()));
+            assert_eq!(iter.nth(1000).map(describe_bead), None);
+            assert_eq!(iter.len(), 0);
+
+            let skipped: Vec<(bool, Vec<u8>)> = beads.iter().skip(123).map(describe_bead).collect();
+            assert_eq!(skipped, all[123..].to_vec());
+            assert_eq!(beads.iter().last().map(describe_bead), all.last().cloned());
+        }
+    }
+}
+
+#[test]
+fn reverse_iteration() {
+    let type_sets = vec![
+        BeadTypeSet::new(&[BeadType::Vlq]),
+        BeadTypeSet::new(&[BeadType::U8, BeadType::None]),
+        BeadTypeSet::new(&[BeadType::U8, BeadType::U16, BeadType::None, BeadType::Utf8]),
+    ];
+    for types in type_sets {
+        let buffer = mixed_sequence(&types, 500);
+        let mut beads = TypedBeads::new(&buffer, &types).ok().unwrap();
+        let all: Vec<(bool, Vec<u8>)> = beads.iter().map(describe_bead).collect();
+        let mut reversed = all.clone();
+        reversed.reverse();
+        for with_index in vec![false, true] {
+            if with_index {
+                beads.build_seek_index(10).ok().unwrap();
+            }
+            let back: Vec<(bool, Vec<u8>)> = beads.iter().rev().map(describe_bead).collect();
+            assert_eq!(back, reversed);
+            let latest: Vec<(bool, Vec<u8>)> = beads.iter().rev().take(3).map(describe_bead).collect();
+            assert_eq!(latest, reversed[..3].to_vec());
+
+            // both ends meet in the middle
+            let mut iter = beads.iter();
+            let mut front = vec![];
+            let mut back = vec![];
+            while let Some(b) = iter.next() {
+                front.push(describe_bead(b));
+                for _ in 0..3 {
+                    if let Some(b) = iter.next_back() {
+                        back.push(describe_bead(b));
+                    }
+                }
+            }
+            back.reverse();
+            front.append(&mut back);
+            assert_eq!(front, all);
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::error::BeadsError;
+
 #[inline]
 pub(crate) fn add_as_vlq(buffer: &mut[u8], mut value: u128) -> usize {
     let mut index = 0;
@@ -11,6 +13,17 @@ pub(crate) fn add_as_vlq(buffer: &mut[u8], mut value: u128) -> usize {
     index
 }
 
+/// Fills the whole buffer with continuation bytes, so a value can be patched in later
+/// without moving the bytes which follow it. The value has to fit into `7 * buffer.len()` bits.
+pub(crate) fn add_as_padded_vlq(buffer: &mut[u8], mut value: u128) {
+    let last = buffer.len() - 1;
+    for b in buffer[..last].iter_mut() {
+        *b = ((value & 127) | 128) as u8;
+        value >>= 7;
+    }
+    buffer[last] = value as u8;
+}
+
 #[inline]
 pub(crate) fn add_as_vlqz(buffer: &mut[u8], value: i128) -> usize {
     add_as_vlq(buffer, zigzag_encode(value))
@@ -22,16 +35,20 @@ fn zigzag_encode(value: i128) -> u128 {
 }
 
 #[inline]
-pub(crate) fn zigzag_decode(value: u128) -> i128 {((value >> 1) as i128) ^ (-1 * ((value & 1) as i128))}
+pub(crate) fn zigzag_decode(value: u128) -> i128 {((value >> 1) as i128) ^ (-((value & 1) as i128))}
 
 #[inline]
-pub(crate) fn read_vlq(buffer: &[u8]) -> Result<(usize, u128), &'static str> {
+pub(crate) fn read_vlq(buffer: &[u8]) -> Result<(usize, u128), BeadsError> {
     let mut result = 0u128;
     let mut index = 0u8;
     loop {
-        let b = buffer.get(index as usize).ok_or("Buffer does not contain a valid vlq")?;
+        let b = buffer.get(index as usize).ok_or(BeadsError::InvalidVlq { offset: 0 })?;
         let b1 = (b & 127) as u128;
         let shift = 7 * index as u128;
+        // the 19th byte can only carry the two highest bits of a u128
+        if shift > 126 || (shift == 126 && b1 > 3) {
+            return Err(BeadsError::InvalidVlq { offset: 0 });
+        }
         result |= b1 << shift;
         if b & 128 == 0 {
             break
@@ -73,7 +90,7 @@ impl VlqByteSize for usize {
 
 #[cfg(test)]
 mod tests {
-    use crate::vlq::{add_as_vlq, zigzag_encode, read_vlq, zigzag_decode};
+    use crate::vlq::{add_as_vlq, add_as_padded_vlq, zigzag_encode, read_vlq, zigzag_decode};
 
     #[test]
     fn add_vlq() {
@@ -108,6 +125,28 @@ mod tests {
         assert_eq!(read_vlq(buf.as_slice()).ok().unwrap(), (3, 22580));
     }
 
+    #[test]
+    fn padded_vlq() {
+        let mut buf = vec![0u8; 4];
+        add_as_padded_vlq(buf.as_mut_slice(), 0);
+        assert_eq!(buf, vec![128, 128, 128, 0]);
+        assert_eq!(read_vlq(buf.as_slice()).ok().unwrap(), (4, 0));
+        add_as_padded_vlq(buf.as_mut_slice(), 22580);
+        assert_eq!(buf, vec![180, 176, 129, 0]);
+        assert_eq!(read_vlq(buf.as_slice()).ok().unwrap(), (4, 22580));
+    }
+
+    #[test]
+    fn read_vlq_rejects_overlong_values() {
+        let mut buf = vec![0u8; 19];
+        assert_eq!(add_as_vlq(buf.as_mut_slice(), u128::MAX), 19);
+        assert_eq!(read_vlq(buf.as_slice()).ok().unwrap(), (19, u128::MAX));
+        buf[18] = 4;
+        assert!(read_vlq(buf.as_slice()).is_err());
+        assert!(read_vlq(&[0xff; 40]).is_err());
+        assert!(read_vlq(&[0x80; 300]).is_err());
+    }
+
     #[test]
     fn zig_zag_encode() {
         assert_eq!(zigzag_encode(0), 0);